@@ -0,0 +1,99 @@
+//! Throughput of the block cipher backends, processing one block at a time
+//! (as a loop over `encrypt_block`) and multiple blocks at once (`encrypt_blocks`).
+//!
+//! ```shell
+//! cargo bench --bench throughput
+//! ```
+//!
+//! Without `--bench` (e.g. `cargo test --all-targets`) every measurement runs only once
+//! on a small input to keep the target working.
+use aes::{Aes128, Aes256, Backend, BlockCipher};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const BACKENDS: &[Backend] = &[
+    Backend::Reference,
+    Backend::TTable,
+    Backend::Bitsliced,
+    Backend::AesNi,
+];
+
+struct Config {
+    data_len: usize,
+    min_duration: Duration,
+}
+
+fn main() {
+    let config = if std::env::args().any(|arg| arg == "--bench") {
+        Config {
+            data_len: 1 << 20,
+            min_duration: Duration::from_secs(1),
+        }
+    } else {
+        Config {
+            data_len: 1 << 10,
+            min_duration: Duration::ZERO,
+        }
+    };
+
+    let data = vec![0x5a; config.data_len];
+    let mut blocks = vec![[0x5a; 16]; config.data_len / 16];
+    for backend in BACKENDS {
+        let aes128 = Aes128::with_backend(&[0x42; 16], *backend);
+        let aes256 = Aes256::with_backend(&[0x42; 32], *backend);
+        if aes128.backend() != *backend {
+            println!("{backend:?} is not supported");
+            continue;
+        }
+
+        report("aes128", *backend, "encrypt", &config, || {
+            encrypt(&aes128, &data)
+        });
+        report("aes128", *backend, "decrypt", &config, || {
+            decrypt(&aes128, &data)
+        });
+        report("aes256", *backend, "encrypt", &config, || {
+            encrypt(&aes256, &data)
+        });
+        report("aes256", *backend, "decrypt", &config, || {
+            decrypt(&aes256, &data)
+        });
+        report("aes128", *backend, "encrypt_blocks", &config, || {
+            aes128.encrypt_blocks(black_box(&mut blocks))
+        });
+        report("aes128", *backend, "decrypt_blocks", &config, || {
+            aes128.decrypt_blocks(black_box(&mut blocks))
+        });
+    }
+}
+
+fn encrypt(cipher: &dyn BlockCipher, data: &[u8]) {
+    for block in data.chunks_exact(16) {
+        black_box(cipher.encrypt_block(black_box(block.try_into().unwrap())));
+    }
+}
+
+fn decrypt(cipher: &dyn BlockCipher, data: &[u8]) {
+    for block in data.chunks_exact(16) {
+        black_box(cipher.decrypt_block(black_box(block.try_into().unwrap())));
+    }
+}
+
+fn report(name: &str, backend: Backend, operation: &str, config: &Config, mut f: impl FnMut()) {
+    let start = Instant::now();
+    let mut iterations = 0;
+    loop {
+        f();
+        iterations += 1;
+        if start.elapsed() >= config.min_duration {
+            break;
+        }
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    let mib = (config.data_len * iterations) as f64 / (1024.0 * 1024.0);
+    println!(
+        "{name} {:<10} {operation:<14}: {:>8.2} MiB/s",
+        format!("{backend:?}"),
+        mib / elapsed
+    );
+}
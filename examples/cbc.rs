@@ -1,8 +1,11 @@
-use aes::{Cipher, Mode, Operation, Padding};
+use aes::{Aes128, Cipher, EntropySource, Mode, Operation, OsEntropy, Padding};
 
 fn main() {
-    let key = "0123456789abcdef".as_bytes();
-    let iv: [u8; 16] = "fedcba9876543210".as_bytes().try_into().unwrap();
+    let aes = Aes128::new(b"0123456789abcdef");
+    // IV has to be unpredictable and never reused with the same key, so it's random for every message.
+    // It isn't secret and is sent along with the ciphertext.
+    let mut iv = [0u8; 16];
+    OsEntropy.fill(&mut iv).unwrap();
     let plaintext = [
         "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua.",
         "Tincidunt tortor aliquam nulla facilisi cras.",
@@ -19,14 +22,14 @@ fn main() {
         "Aliquam purus sit amet luctus venenatis."];
 
     // Encrypt data
-    let mut cipher = match Cipher::init(Operation::Encrypt, key, Mode::Cbc(iv), Padding::PKCS7) {
+    let mut cipher = match Cipher::init(Operation::Encrypt, &aes, Mode::Cbc(iv), Padding::PKCS7) {
         Ok(c) => c,
         Err(err) => panic!("{:?}", err),
     };
 
     let mut ciphertext = Vec::<u8>::new();
     for p in plaintext {
-        let c = cipher.update(p.as_bytes());
+        let c = cipher.update(p.as_bytes()).unwrap();
         ciphertext.extend(c);
     }
     let c = match cipher.finalize() {
@@ -36,12 +39,12 @@ fn main() {
     ciphertext.extend(c);
 
     // Decrypt data
-    let mut cipher = match Cipher::init(Operation::Decrypt, key, Mode::Cbc(iv), Padding::PKCS7) {
+    let mut cipher = match Cipher::init(Operation::Decrypt, &aes, Mode::Cbc(iv), Padding::PKCS7) {
         Ok(c) => c,
         Err(err) => panic!("{:?}", err),
     };
     let mut recovered = Vec::<u8>::with_capacity(ciphertext.len());
-    let c = cipher.update(&ciphertext);
+    let c = cipher.update(&ciphertext).unwrap();
     recovered.extend(c);
     let c = match cipher.finalize() {
         Ok(f) => f,
@@ -63,5 +66,9 @@ fn main() {
     // recovered plaintext is the same as one before encryption
     assert_eq!(recovered, plaintext);
 
+    // seal/open do all of the above in one step, IV is stored in front of the ciphertext.
+    let sealed = aes::seal(&aes, &plaintext).unwrap();
+    assert_eq!(aes::open(&aes, &sealed).unwrap(), plaintext);
+
     println!("CBC mode works fine!");
 }
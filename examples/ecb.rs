@@ -1,7 +1,7 @@
-use aes::{Cipher, Mode, Operation, Padding};
+use aes::{Aes128, Cipher, Mode, Operation, Padding};
 
 fn main() {
-    let key = "0123456789abcdef".as_bytes();
+    let aes = Aes128::new(b"0123456789abcdef");
     let plaintext = [
         "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua.",
         "Tincidunt tortor aliquam nulla facilisi cras.",
@@ -18,14 +18,14 @@ fn main() {
         "Aliquam purus sit amet luctus venenatis."];
 
     // Encrypt data
-    let mut cipher = match Cipher::init(Operation::Encrypt, key, Mode::Ecb, Padding::PKCS7) {
+    let mut cipher = match Cipher::init(Operation::Encrypt, &aes, Mode::Ecb, Padding::PKCS7) {
         Ok(c) => c,
         Err(err) => panic!("{:?}", err),
     };
 
     let mut ciphertext = Vec::<u8>::new();
     for p in plaintext {
-        let c = cipher.update(p.as_bytes());
+        let c = cipher.update(p.as_bytes()).unwrap();
         ciphertext.extend(c);
     }
     let c = match cipher.finalize() {
@@ -35,12 +35,12 @@ fn main() {
     ciphertext.extend(c);
 
     // Decrypt data
-    let mut cipher = match Cipher::init(Operation::Decrypt, key, Mode::Ecb, Padding::PKCS7) {
+    let mut cipher = match Cipher::init(Operation::Decrypt, &aes, Mode::Ecb, Padding::PKCS7) {
         Ok(c) => c,
         Err(err) => panic!("{:?}", err),
     };
     let mut recovered = Vec::<u8>::with_capacity(ciphertext.len());
-    let c = cipher.update(&ciphertext);
+    let c = cipher.update(&ciphertext).unwrap();
     recovered.extend(c);
     let c = match cipher.finalize() {
         Ok(f) => f,
@@ -0,0 +1,78 @@
+/*
+ * C API of the aes crate. Maintained by hand, keep in sync with ffi/src/lib.rs.
+ *
+ * Link with libaes_ffi.a (plus -lpthread -ldl -lm on Linux) or libaes_ffi.so.
+ */
+#ifndef AES_H
+#define AES_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Status codes returned by the functions below. */
+#define AES_OK 0
+#define AES_ERR_NULL_POINTER 1
+#define AES_ERR_INVALID_ARGUMENT 2
+#define AES_ERR_INVALID_KEY_LENGTH 3
+#define AES_ERR_INVALID_IV_LENGTH 4
+#define AES_ERR_INVALID_PADDING 5
+#define AES_ERR_TRUNCATED_INPUT 6
+#define AES_ERR_OUTPUT_TOO_SMALL 7
+#define AES_ERR_INTERNAL 8
+
+#define AES_ENCRYPT 0
+#define AES_DECRYPT 1
+
+#define AES_MODE_ECB 0
+#define AES_MODE_CBC 1
+
+#define AES_PADDING_PKCS7 0
+#define AES_PADDING_NONE 1
+
+#define AES_BLOCK_SIZE 16
+
+/* Opaque cipher handle. */
+typedef struct AesCipher aes_cipher;
+
+/*
+ * Creates a cipher for a 16, 24 or 32 byte key and stores its handle in *out.
+ * iv has to be AES_BLOCK_SIZE bytes for CBC, it is ignored for ECB.
+ */
+int aes_cipher_new(int operation, const uint8_t *key, size_t key_len, int mode,
+                   const uint8_t *iv, size_t iv_len, int padding, aes_cipher **out);
+
+/*
+ * Stores the exact number of bytes written by aes_cipher_update with input_len bytes of input
+ * in *len. Lengths beyond SIZE_MAX saturate.
+ */
+int aes_cipher_update_len(aes_cipher *cipher, size_t input_len, size_t *len);
+
+/* Stores the maximum number of bytes written by aes_cipher_finalize in *len. */
+int aes_cipher_finalize_len(aes_cipher *cipher, size_t *len);
+
+/* Processes input, the number of bytes written to output is stored in *written. */
+int aes_cipher_update(aes_cipher *cipher, const uint8_t *input, size_t input_len,
+                      uint8_t *output, size_t output_len, size_t *written);
+
+/* Processes the last block, the number of bytes written to output is stored in *written. */
+int aes_cipher_finalize(aes_cipher *cipher, uint8_t *output, size_t output_len,
+                        size_t *written);
+
+/* Starts a new message with the same key, iv has to be empty (iv_len 0) for ECB. */
+int aes_cipher_reset(aes_cipher *cipher, const uint8_t *iv, size_t iv_len);
+
+/* Releases the handle and wipes key material. NULL is ignored. */
+void aes_cipher_free(aes_cipher *cipher);
+
+/* Static description of the status code, NULL for unknown codes. */
+const char *aes_status_message(int status);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* AES_H */
@@ -0,0 +1,246 @@
+//! C API of the `aes` crate, declared in `include/aes.h`.
+//!
+//! Ciphers are opaque handles created with [`aes_cipher_new`] and released with
+//! [`aes_cipher_free`]. Functions return [`AES_OK`] or one of the `AES_ERR_*` codes
+//! and write into buffers provided by the caller.
+use std::{
+    os::raw::{c_char, c_int},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use aes::{Cipher, CipherError, Key, Mode, Operation, Padding};
+
+pub const AES_OK: c_int = 0;
+pub const AES_ERR_NULL_POINTER: c_int = 1;
+pub const AES_ERR_INVALID_ARGUMENT: c_int = 2;
+pub const AES_ERR_INVALID_KEY_LENGTH: c_int = 3;
+pub const AES_ERR_INVALID_IV_LENGTH: c_int = 4;
+pub const AES_ERR_INVALID_PADDING: c_int = 5;
+pub const AES_ERR_TRUNCATED_INPUT: c_int = 6;
+pub const AES_ERR_OUTPUT_TOO_SMALL: c_int = 7;
+pub const AES_ERR_INTERNAL: c_int = 8;
+
+pub const AES_ENCRYPT: c_int = 0;
+pub const AES_DECRYPT: c_int = 1;
+
+pub const AES_MODE_ECB: c_int = 0;
+pub const AES_MODE_CBC: c_int = 1;
+
+pub const AES_PADDING_PKCS7: c_int = 0;
+pub const AES_PADDING_NONE: c_int = 1;
+
+/// Opaque cipher handle, `aes_cipher` in C.
+pub struct AesCipher {
+    cipher: Cipher<'static>,
+}
+
+fn status(err: CipherError) -> c_int {
+    match err {
+        CipherError::InvalidKeyLength { .. } | CipherError::KeySizeMismatch { .. } => {
+            AES_ERR_INVALID_KEY_LENGTH
+        }
+        CipherError::InvalidIvLength { .. } => AES_ERR_INVALID_IV_LENGTH,
+        CipherError::InvalidPadding => AES_ERR_INVALID_PADDING,
+        CipherError::TruncatedInput => AES_ERR_TRUNCATED_INPUT,
+        CipherError::OutputTooSmall { .. } => AES_ERR_OUTPUT_TOO_SMALL,
+        _ => AES_ERR_INTERNAL,
+    }
+}
+
+/// Runs `f`, so that neither an error nor a panic crosses the C boundary.
+fn guard(f: impl FnOnce() -> Result<(), c_int>) -> c_int {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => AES_OK,
+        Ok(Err(code)) => code,
+        Err(_) => AES_ERR_INTERNAL,
+    }
+}
+
+/// Slice from a pointer and length, null is allowed only for empty slices.
+unsafe fn input<'a>(data: *const u8, len: usize) -> Result<&'a [u8], c_int> {
+    match (data.is_null(), len) {
+        (_, 0) => Ok(&[]),
+        (true, _) => Err(AES_ERR_NULL_POINTER),
+        (false, _) => Ok(slice::from_raw_parts(data, len)),
+    }
+}
+
+unsafe fn output<'a>(data: *mut u8, len: usize) -> Result<&'a mut [u8], c_int> {
+    match (data.is_null(), len) {
+        (_, 0) => Ok(&mut []),
+        (true, _) => Err(AES_ERR_NULL_POINTER),
+        (false, _) => Ok(slice::from_raw_parts_mut(data, len)),
+    }
+}
+
+unsafe fn handle<'a>(cipher: *mut AesCipher) -> Result<&'a mut Cipher<'static>, c_int> {
+    cipher
+        .as_mut()
+        .map(|handle| &mut handle.cipher)
+        .ok_or(AES_ERR_NULL_POINTER)
+}
+
+/// Creates a cipher and stores its handle in `*out`. `iv` is ignored for ECB.
+///
+/// # Safety
+/// Pointers have to be valid for their lengths, `out` has to be writable.
+#[no_mangle]
+pub unsafe extern "C" fn aes_cipher_new(
+    operation: c_int,
+    key: *const u8,
+    key_len: usize,
+    mode: c_int,
+    iv: *const u8,
+    iv_len: usize,
+    padding: c_int,
+    out: *mut *mut AesCipher,
+) -> c_int {
+    guard(|| {
+        if out.is_null() {
+            return Err(AES_ERR_NULL_POINTER);
+        }
+        let operation = match operation {
+            AES_ENCRYPT => Operation::Encrypt,
+            AES_DECRYPT => Operation::Decrypt,
+            _ => return Err(AES_ERR_INVALID_ARGUMENT),
+        };
+        let mode = match mode {
+            AES_MODE_ECB => Mode::Ecb,
+            AES_MODE_CBC => Mode::cbc(input(iv, iv_len)?).map_err(status)?,
+            _ => return Err(AES_ERR_INVALID_ARGUMENT),
+        };
+        let padding = match padding {
+            AES_PADDING_PKCS7 => Padding::PKCS7,
+            AES_PADDING_NONE => Padding::NoPadding,
+            _ => return Err(AES_ERR_INVALID_ARGUMENT),
+        };
+        let key = Key::new(input(key, key_len)?).map_err(status)?;
+        let cipher = Cipher::with_key(operation, &key, mode, padding).map_err(status)?;
+        *out = Box::into_raw(Box::new(AesCipher { cipher }));
+        Ok(())
+    })
+}
+
+/// Stores the exact number of bytes written by [`aes_cipher_update`] with `input_len` bytes
+/// of input in `*len`.
+///
+/// # Safety
+/// `cipher` has to be a handle returned by [`aes_cipher_new`], `len` has to be writable.
+#[no_mangle]
+pub unsafe extern "C" fn aes_cipher_update_len(
+    cipher: *mut AesCipher,
+    input_len: usize,
+    len: *mut usize,
+) -> c_int {
+    guard(|| {
+        let cipher = handle(cipher)?;
+        let len = len.as_mut().ok_or(AES_ERR_NULL_POINTER)?;
+        *len = cipher.update_len(input_len);
+        Ok(())
+    })
+}
+
+/// Stores the maximum number of bytes written by [`aes_cipher_finalize`] in `*len`.
+///
+/// # Safety
+/// Same as [`aes_cipher_update_len`].
+#[no_mangle]
+pub unsafe extern "C" fn aes_cipher_finalize_len(cipher: *mut AesCipher, len: *mut usize) -> c_int {
+    guard(|| {
+        let cipher = handle(cipher)?;
+        let len = len.as_mut().ok_or(AES_ERR_NULL_POINTER)?;
+        *len = cipher.finalize_len();
+        Ok(())
+    })
+}
+
+/// Processes `input` and stores the number of bytes written to `output` in `*written`.
+///
+/// # Safety
+/// `cipher` has to be a handle returned by [`aes_cipher_new`], pointers have to be valid
+/// for their lengths and `written` has to be writable.
+#[no_mangle]
+pub unsafe extern "C" fn aes_cipher_update(
+    cipher: *mut AesCipher,
+    input_data: *const u8,
+    input_len: usize,
+    output_data: *mut u8,
+    output_len: usize,
+    written: *mut usize,
+) -> c_int {
+    guard(|| {
+        let cipher = handle(cipher)?;
+        let written = written.as_mut().ok_or(AES_ERR_NULL_POINTER)?;
+        let input = input(input_data, input_len)?;
+        let output = output(output_data, output_len)?;
+        *written = cipher.update_into(input, output).map_err(status)?;
+        Ok(())
+    })
+}
+
+/// Processes the last block and stores the number of bytes written in `*written`.
+/// The handle can be reused for the next message after [`aes_cipher_reset`].
+///
+/// # Safety
+/// Same as [`aes_cipher_update`].
+#[no_mangle]
+pub unsafe extern "C" fn aes_cipher_finalize(
+    cipher: *mut AesCipher,
+    output_data: *mut u8,
+    output_len: usize,
+    written: *mut usize,
+) -> c_int {
+    guard(|| {
+        let cipher = handle(cipher)?;
+        let written = written.as_mut().ok_or(AES_ERR_NULL_POINTER)?;
+        let output = output(output_data, output_len)?;
+        *written = cipher.finalize_into(output).map_err(status)?;
+        Ok(())
+    })
+}
+
+/// Starts a new message with the same key, `iv` has to be empty for ECB.
+///
+/// # Safety
+/// `cipher` has to be a handle returned by [`aes_cipher_new`], `iv` has to be valid for `iv_len`.
+#[no_mangle]
+pub unsafe extern "C" fn aes_cipher_reset(
+    cipher: *mut AesCipher,
+    iv: *const u8,
+    iv_len: usize,
+) -> c_int {
+    guard(|| {
+        let cipher = handle(cipher)?;
+        cipher.reset(input(iv, iv_len)?).map_err(status)
+    })
+}
+
+/// Releases the handle, round keys and buffered bytes are wiped. Null is ignored.
+///
+/// # Safety
+/// `cipher` has to be null or a handle returned by [`aes_cipher_new`], not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn aes_cipher_free(cipher: *mut AesCipher) {
+    if !cipher.is_null() {
+        drop(Box::from_raw(cipher));
+    }
+}
+
+/// Static, NUL terminated description of the status code.
+#[no_mangle]
+pub extern "C" fn aes_status_message(status: c_int) -> *const c_char {
+    let message: &'static [u8] = match status {
+        AES_OK => b"ok\0",
+        AES_ERR_NULL_POINTER => b"null pointer\0",
+        AES_ERR_INVALID_ARGUMENT => b"invalid operation, mode or padding\0",
+        AES_ERR_INVALID_KEY_LENGTH => b"invalid key length\0",
+        AES_ERR_INVALID_IV_LENGTH => b"invalid initialization vector length\0",
+        AES_ERR_INVALID_PADDING => b"invalid padding\0",
+        AES_ERR_TRUNCATED_INPUT => b"ciphertext length is not a multiple of block size\0",
+        AES_ERR_OUTPUT_TOO_SMALL => b"output buffer is too small\0",
+        AES_ERR_INTERNAL => b"internal error\0",
+        _ => return ptr::null(),
+    };
+    message.as_ptr().cast()
+}
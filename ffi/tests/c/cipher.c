@@ -0,0 +1,141 @@
+/* Exercises the C API, exits with a non-zero status on the first failed check. */
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "aes.h"
+
+#define CHECK(condition)                                                     \
+    do {                                                                     \
+        if (!(condition)) {                                                  \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, \
+                    #condition);                                             \
+            return 1;                                                        \
+        }                                                                    \
+    } while (0)
+
+static const uint8_t KEY[16] = {0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+                                0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f};
+static const uint8_t IV[16] = {0x24, 0x24, 0x24, 0x24, 0x24, 0x24, 0x24, 0x24,
+                               0x24, 0x24, 0x24, 0x24, 0x24, 0x24, 0x24, 0x24};
+
+/* Processes input in chunks of chunk_len bytes, returns the output length or -1. */
+static long process(aes_cipher *cipher, const uint8_t *input, size_t input_len,
+                    size_t chunk_len, uint8_t *output, size_t output_len) {
+    size_t total = 0, written = 0, update_len = 0;
+    for (size_t offset = 0; offset < input_len; offset += chunk_len) {
+        size_t len = input_len - offset < chunk_len ? input_len - offset : chunk_len;
+        if (aes_cipher_update_len(cipher, len, &update_len) != AES_OK) return -1;
+        if (update_len > output_len - total) return -1;
+        if (aes_cipher_update(cipher, input + offset, len, output + total, output_len - total,
+                              &written) != AES_OK)
+            return -1;
+        total += written;
+    }
+    if (aes_cipher_finalize(cipher, output + total, output_len - total, &written) != AES_OK)
+        return -1;
+    return (long)(total + written);
+}
+
+/* FIPS-197 appendix C.1. */
+static int known_answer(void) {
+    const uint8_t plaintext[16] = {0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77,
+                                   0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff};
+    const uint8_t expected[16] = {0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30,
+                                  0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4, 0xc5, 0x5a};
+    aes_cipher *cipher = NULL;
+    uint8_t output[16];
+    CHECK(aes_cipher_new(AES_ENCRYPT, KEY, sizeof KEY, AES_MODE_ECB, NULL, 0, AES_PADDING_NONE,
+                         &cipher) == AES_OK);
+    CHECK(process(cipher, plaintext, sizeof plaintext, 16, output, sizeof output) == 16);
+    CHECK(memcmp(expected, output, 16) == 0);
+    aes_cipher_free(cipher);
+    return 0;
+}
+
+static int cbc_round_trip(void) {
+    uint8_t plaintext[100], ciphertext[112], recovered[112];
+    for (size_t i = 0; i < sizeof plaintext; i++) plaintext[i] = (uint8_t)(i * 7);
+
+    aes_cipher *encryptor = NULL, *decryptor = NULL;
+    CHECK(aes_cipher_new(AES_ENCRYPT, KEY, sizeof KEY, AES_MODE_CBC, IV, sizeof IV,
+                         AES_PADDING_PKCS7, &encryptor) == AES_OK);
+    CHECK(aes_cipher_new(AES_DECRYPT, KEY, sizeof KEY, AES_MODE_CBC, IV, sizeof IV,
+                         AES_PADDING_PKCS7, &decryptor) == AES_OK);
+    size_t finalize_len = 0;
+    CHECK(aes_cipher_finalize_len(encryptor, &finalize_len) == AES_OK);
+    CHECK(finalize_len == 16);
+
+    const size_t chunks[] = {1, 7, 16, 100};
+    long expected_len = -1;
+    uint8_t expected[112];
+    for (size_t i = 0; i < sizeof chunks / sizeof chunks[0]; i++) {
+        CHECK(aes_cipher_reset(encryptor, IV, sizeof IV) == AES_OK);
+        long len = process(encryptor, plaintext, sizeof plaintext, chunks[i], ciphertext,
+                           sizeof ciphertext);
+        CHECK(len == 112);
+        if (expected_len < 0) {
+            expected_len = len;
+            memcpy(expected, ciphertext, (size_t)len);
+        }
+        CHECK(memcmp(expected, ciphertext, (size_t)len) == 0);
+
+        CHECK(aes_cipher_reset(decryptor, IV, sizeof IV) == AES_OK);
+        CHECK(process(decryptor, ciphertext, (size_t)len, chunks[i], recovered,
+                      sizeof recovered) == 100);
+        CHECK(memcmp(plaintext, recovered, sizeof plaintext) == 0);
+    }
+    aes_cipher_free(encryptor);
+    aes_cipher_free(decryptor);
+    return 0;
+}
+
+static int errors(void) {
+    aes_cipher *cipher = NULL;
+    size_t written = 0;
+    uint8_t output[32];
+
+    CHECK(aes_cipher_new(AES_ENCRYPT, KEY, 15, AES_MODE_ECB, NULL, 0, AES_PADDING_PKCS7,
+                         &cipher) == AES_ERR_INVALID_KEY_LENGTH);
+    CHECK(aes_cipher_new(AES_ENCRYPT, KEY, sizeof KEY, AES_MODE_CBC, IV, 8, AES_PADDING_PKCS7,
+                         &cipher) == AES_ERR_INVALID_IV_LENGTH);
+    CHECK(aes_cipher_new(AES_ENCRYPT, KEY, sizeof KEY, 7, NULL, 0, AES_PADDING_PKCS7,
+                         &cipher) == AES_ERR_INVALID_ARGUMENT);
+    CHECK(aes_cipher_new(AES_ENCRYPT, NULL, 16, AES_MODE_ECB, NULL, 0, AES_PADDING_PKCS7,
+                         &cipher) == AES_ERR_NULL_POINTER);
+    CHECK(cipher == NULL);
+
+    CHECK(aes_cipher_new(AES_ENCRYPT, KEY, sizeof KEY, AES_MODE_ECB, NULL, 0, AES_PADDING_PKCS7,
+                         &cipher) == AES_OK);
+    CHECK(aes_cipher_update(cipher, KEY, 16, output, 8, &written) ==
+          AES_ERR_OUTPUT_TOO_SMALL);
+    CHECK(aes_cipher_update(NULL, KEY, 16, output, sizeof output, &written) ==
+          AES_ERR_NULL_POINTER);
+    CHECK(aes_cipher_reset(cipher, IV, sizeof IV) == AES_ERR_INVALID_IV_LENGTH);
+    aes_cipher_free(cipher);
+
+    CHECK(aes_cipher_new(AES_DECRYPT, KEY, sizeof KEY, AES_MODE_ECB, NULL, 0, AES_PADDING_PKCS7,
+                         &cipher) == AES_OK);
+    CHECK(aes_cipher_update(cipher, KEY, 5, output, sizeof output, &written) == AES_OK);
+    CHECK(written == 0);
+    /* Length of the buffered bytes plus SIZE_MAX saturates instead of wrapping around. */
+    size_t len = 0;
+    CHECK(aes_cipher_update_len(cipher, SIZE_MAX, &len) == AES_OK);
+    CHECK(len == SIZE_MAX - SIZE_MAX % AES_BLOCK_SIZE);
+    CHECK(aes_cipher_update_len(NULL, 16, &len) == AES_ERR_NULL_POINTER);
+    CHECK(aes_cipher_finalize_len(cipher, NULL) == AES_ERR_NULL_POINTER);
+    CHECK(aes_cipher_finalize(cipher, output, sizeof output, &written) ==
+          AES_ERR_TRUNCATED_INPUT);
+    aes_cipher_free(cipher);
+    aes_cipher_free(NULL);
+
+    CHECK(strcmp("invalid padding", aes_status_message(AES_ERR_INVALID_PADDING)) == 0);
+    CHECK(aes_status_message(-1) == NULL);
+    return 0;
+}
+
+int main(void) {
+    if (known_answer() || cbc_round_trip() || errors()) return 1;
+    puts("ok");
+    return 0;
+}
@@ -0,0 +1,35 @@
+//! Compiles `tests/c/cipher.c` with the system `cc` against the static library and runs it.
+#![cfg(target_os = "linux")]
+
+use std::{env, path::PathBuf, process::Command};
+
+#[test]
+fn c_program_passes() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // Library is built next to the test binary, in target/<profile>/deps.
+    let library = env::current_exe().unwrap().with_file_name("libaes_ffi.a");
+    assert!(library.exists(), "{} not built", library.display());
+
+    let program = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("c_api_cipher");
+    let status = Command::new("cc")
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg(manifest_dir.join("tests/c/cipher.c"))
+        .arg(&library)
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&program)
+        .status()
+        .expect("cc is not available");
+    assert!(status.success(), "compilation failed");
+
+    let output = Command::new(&program).output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!("ok\n", String::from_utf8_lossy(&output.stdout));
+}
@@ -1,61 +1,394 @@
-use crate::{dec, enc, key, state, Block};
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+use crate::aesni;
+use crate::{
+    bitslice, dec, enc, key, key::KeySchedule, state, ttable, zeroize::zeroize, Block, CipherError,
+    Mode,
+};
 
-pub(crate) fn encrypt_block(plaintext: &Block, key: &[u8]) -> Block {
-    let nr = get_nr(key.len() * 8);
+/// Block cipher with an already expanded key.
+///
+/// Instances are immutable after construction, so a single instance can be shared between
+/// threads and used by any number of [`Cipher`](crate::Cipher)s at once.
+pub trait BlockCipher: Send + Sync {
+    fn encrypt_block(&self, block: &Block) -> Block;
+    fn decrypt_block(&self, block: &Block) -> Block;
+
+    /// Encrypts independent blocks in place. Keyed ciphers interleave rounds of several
+    /// blocks (4 with T-tables and bitslicing, 8 with AES-NI), which is faster than calling
+    /// [`BlockCipher::encrypt_block`] in a loop. [`Backend::Reference`] deliberately stays
+    /// per block, so it keeps following FIPS-197 step by step.
+    fn encrypt_blocks(&self, blocks: &mut [Block]) {
+        for block in blocks {
+            *block = self.encrypt_block(block);
+        }
+    }
+
+    /// Decrypts independent blocks in place, see [`BlockCipher::encrypt_blocks`].
+    fn decrypt_blocks(&self, blocks: &mut [Block]) {
+        for block in blocks {
+            *block = self.decrypt_block(block);
+        }
+    }
+
+    /// Called by the encrypting [`Cipher`](crate::Cipher) when a message starts (`messages` is 1)
+    /// and before `blocks` are encrypted in the `mode`. Failing stops the encryption, see
+    /// [`MeteredCipher`](crate::MeteredCipher). Accepts everything by default.
+    fn consume(&self, mode: &Mode, messages: u64, blocks: u64) -> Result<(), CipherError> {
+        let _ = (mode, messages, blocks);
+        Ok(())
+    }
+}
+
+/// Implementation of the round function used by keyed ciphers.
+///
+/// Default backend is [`Backend::AesNi`] when the processor supports it,
+/// [`Backend::Reference`] otherwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// Byte oriented implementation following FIPS-197 step by step.
+    Reference,
+    /// SubBytes, ShiftRows and MixColumns fused into 32-bit table lookups.
+    /// Much faster, but memory access depends on the key and the data.
+    TTable,
+    /// Constant-time implementation using only bitwise operations on four blocks at once,
+    /// including the key expansion.
+    Bitsliced,
+    /// Hardware AES instructions of x86_64 processors. Without `std` they have to be enabled
+    /// for the target, as they can't be detected at runtime. Falls back to [`Backend::Reference`] when they are not available.
+    AesNi,
+}
+
+#[allow(clippy::derivable_impls)] // not derivable when AES-NI is available
+impl Default for Backend {
+    fn default() -> Self {
+        #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+        if aesni::is_supported() {
+            return Backend::AesNi;
+        }
+        Backend::Reference
+    }
+}
+
+#[allow(clippy::large_enum_variant)] // built once per key, boxing would require allocation
+enum Engine {
+    Reference(KeySchedule),
+    TTable(ttable::KeySchedule),
+    Bitsliced(bitslice::KeySchedule),
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    AesNi(aesni::KeySchedule),
+}
+
+impl Engine {
+    fn new(key: &[u8], backend: Backend) -> Self {
+        match backend {
+            Backend::Reference => Engine::Reference(KeySchedule::new(key)),
+            Backend::TTable => Engine::TTable(ttable::KeySchedule::new(&KeySchedule::new(key))),
+            Backend::Bitsliced => Engine::Bitsliced(bitslice::KeySchedule::new(key)),
+            #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+            Backend::AesNi => match aesni::KeySchedule::new(key) {
+                Some(key_schedule) => Engine::AesNi(key_schedule),
+                None => Engine::Reference(KeySchedule::new(key)),
+            },
+            #[cfg(not(all(target_arch = "x86_64", target_feature = "sse2")))]
+            Backend::AesNi => Engine::Reference(KeySchedule::new(key)),
+        }
+    }
+
+    fn backend(&self) -> Backend {
+        match self {
+            Engine::Reference(_) => Backend::Reference,
+            Engine::TTable(_) => Backend::TTable,
+            Engine::Bitsliced(_) => Backend::Bitsliced,
+            #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+            Engine::AesNi(_) => Backend::AesNi,
+        }
+    }
+
+    fn encrypt_block(&self, block: &Block) -> Block {
+        match self {
+            Engine::Reference(key_schedule) => encrypt_block(block, key_schedule),
+            Engine::TTable(key_schedule) => ttable::encrypt_block(block, key_schedule),
+            Engine::Bitsliced(key_schedule) => bitslice::encrypt_block(block, key_schedule),
+            #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+            Engine::AesNi(key_schedule) => aesni::encrypt_block(block, key_schedule),
+        }
+    }
+
+    fn decrypt_block(&self, block: &Block) -> Block {
+        match self {
+            Engine::Reference(key_schedule) => decrypt_block(block, key_schedule),
+            Engine::TTable(key_schedule) => ttable::decrypt_block(block, key_schedule),
+            Engine::Bitsliced(key_schedule) => bitslice::decrypt_block(block, key_schedule),
+            #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+            Engine::AesNi(key_schedule) => aesni::decrypt_block(block, key_schedule),
+        }
+    }
+
+    fn encrypt_blocks(&self, blocks: &mut [Block]) {
+        match self {
+            // Not interleaved, see `BlockCipher::encrypt_blocks`.
+            Engine::Reference(key_schedule) => {
+                for block in blocks {
+                    *block = encrypt_block(block, key_schedule);
+                }
+            }
+            Engine::TTable(key_schedule) => ttable::encrypt_blocks(blocks, key_schedule),
+            Engine::Bitsliced(key_schedule) => bitslice::encrypt_blocks(blocks, key_schedule),
+            #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+            Engine::AesNi(key_schedule) => aesni::encrypt_blocks(blocks, key_schedule),
+        }
+    }
+
+    fn decrypt_blocks(&self, blocks: &mut [Block]) {
+        match self {
+            // Not interleaved, see `BlockCipher::encrypt_blocks`.
+            Engine::Reference(key_schedule) => {
+                for block in blocks {
+                    *block = decrypt_block(block, key_schedule);
+                }
+            }
+            Engine::TTable(key_schedule) => ttable::decrypt_blocks(blocks, key_schedule),
+            Engine::Bitsliced(key_schedule) => bitslice::decrypt_blocks(blocks, key_schedule),
+            #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+            Engine::AesNi(key_schedule) => aesni::decrypt_blocks(blocks, key_schedule),
+        }
+    }
+}
+
+/// AES with 128-bit key. Round keys are expanded once on construction
+/// for the selected [`Backend`].
+pub struct Aes128 {
+    engine: Engine,
+}
+
+impl Aes128 {
+    pub fn new(key: &[u8; 16]) -> Self {
+        Self::with_backend(key, Backend::default())
+    }
+
+    /// Same as [`Aes128::new`], but the key length is checked at runtime.
+    pub fn new_from_slice(key: &[u8]) -> Result<Self, CipherError> {
+        match key.try_into() {
+            Ok(key) => Ok(Self::new(key)),
+            Err(_) => Err(CipherError::InvalidKeyLength {
+                bits: key.len() * 8,
+            }),
+        }
+    }
+
+    pub fn with_backend(key: &[u8; 16], backend: Backend) -> Self {
+        Self {
+            engine: Engine::new(key, backend),
+        }
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.engine.backend()
+    }
+}
+
+impl BlockCipher for Aes128 {
+    fn encrypt_block(&self, block: &Block) -> Block {
+        self.engine.encrypt_block(block)
+    }
+
+    fn decrypt_block(&self, block: &Block) -> Block {
+        self.engine.decrypt_block(block)
+    }
+
+    fn encrypt_blocks(&self, blocks: &mut [Block]) {
+        self.engine.encrypt_blocks(blocks)
+    }
+
+    fn decrypt_blocks(&self, blocks: &mut [Block]) {
+        self.engine.decrypt_blocks(blocks)
+    }
+}
+
+/// AES with 192-bit key. Round keys are expanded once on construction
+/// for the selected [`Backend`].
+pub struct Aes192 {
+    engine: Engine,
+}
+
+impl Aes192 {
+    pub fn new(key: &[u8; 24]) -> Self {
+        Self::with_backend(key, Backend::default())
+    }
+
+    /// Same as [`Aes192::new`], but the key length is checked at runtime.
+    pub fn new_from_slice(key: &[u8]) -> Result<Self, CipherError> {
+        match key.try_into() {
+            Ok(key) => Ok(Self::new(key)),
+            Err(_) => Err(CipherError::InvalidKeyLength {
+                bits: key.len() * 8,
+            }),
+        }
+    }
+
+    pub fn with_backend(key: &[u8; 24], backend: Backend) -> Self {
+        Self {
+            engine: Engine::new(key, backend),
+        }
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.engine.backend()
+    }
+}
+
+impl BlockCipher for Aes192 {
+    fn encrypt_block(&self, block: &Block) -> Block {
+        self.engine.encrypt_block(block)
+    }
+
+    fn decrypt_block(&self, block: &Block) -> Block {
+        self.engine.decrypt_block(block)
+    }
+
+    fn encrypt_blocks(&self, blocks: &mut [Block]) {
+        self.engine.encrypt_blocks(blocks)
+    }
+
+    fn decrypt_blocks(&self, blocks: &mut [Block]) {
+        self.engine.decrypt_blocks(blocks)
+    }
+}
+
+/// AES with 256-bit key. Round keys are expanded once on construction
+/// for the selected [`Backend`].
+pub struct Aes256 {
+    engine: Engine,
+}
+
+impl Aes256 {
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self::with_backend(key, Backend::default())
+    }
+
+    /// Same as [`Aes256::new`], but the key length is checked at runtime.
+    pub fn new_from_slice(key: &[u8]) -> Result<Self, CipherError> {
+        match key.try_into() {
+            Ok(key) => Ok(Self::new(key)),
+            Err(_) => Err(CipherError::InvalidKeyLength {
+                bits: key.len() * 8,
+            }),
+        }
+    }
+
+    pub fn with_backend(key: &[u8; 32], backend: Backend) -> Self {
+        Self {
+            engine: Engine::new(key, backend),
+        }
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.engine.backend()
+    }
+}
+
+impl BlockCipher for Aes256 {
+    fn encrypt_block(&self, block: &Block) -> Block {
+        self.engine.encrypt_block(block)
+    }
+
+    fn decrypt_block(&self, block: &Block) -> Block {
+        self.engine.decrypt_block(block)
+    }
+
+    fn encrypt_blocks(&self, blocks: &mut [Block]) {
+        self.engine.encrypt_blocks(blocks)
+    }
+
+    fn decrypt_blocks(&self, blocks: &mut [Block]) {
+        self.engine.decrypt_blocks(blocks)
+    }
+}
+
+fn encrypt_block(plaintext: &Block, key_schedule: &KeySchedule) -> Block {
+    let nr = key_schedule.nr();
+    let round_keys = key_schedule.round_keys();
     let mut state = state::state_from_bytes(plaintext);
-    let key_schedule = key::key_expansion(key, nr as usize);
 
-    key::add_round_key(&mut state, &key_schedule, 0);
+    key::add_round_key(&mut state, round_keys, 0);
 
     for round in 1..nr {
         enc::sub_bytes(&mut state);
         enc::shift_rows(&mut state);
         enc::mix_columns(&mut state);
-        key::add_round_key(&mut state, &key_schedule, round);
+        key::add_round_key(&mut state, round_keys, round);
     }
 
     enc::sub_bytes(&mut state);
     enc::shift_rows(&mut state);
-    key::add_round_key(&mut state, &key_schedule, nr);
+    key::add_round_key(&mut state, round_keys, nr);
 
-    state::state_to_bytes(state)
+    let ciphertext = state::state_to_bytes(state);
+    zeroize(&mut state);
+    ciphertext
 }
 
-pub(crate) fn decrypt_block(ciphertext: &Block, key: &[u8]) -> Block {
-    let nr = get_nr(key.len() * 8);
+// Equivalent inverse cipher, InvMixColumns is already applied to the decryption round keys.
+fn decrypt_block(ciphertext: &Block, key_schedule: &KeySchedule) -> Block {
+    let nr = key_schedule.nr();
+    let dec_round_keys = key_schedule.dec_round_keys();
     let mut state = state::state_from_bytes(ciphertext);
-    let key_schedule = key::key_expansion(key, nr as usize);
 
-    key::add_round_key(&mut state, &key_schedule, nr);
+    key::add_round_key(&mut state, dec_round_keys, 0);
 
-    for round in (1..nr).rev() {
-        dec::shift_rows(&mut state);
+    for round in 1..nr {
         dec::sub_bytes(&mut state);
-        key::add_round_key(&mut state, &key_schedule, round);
+        dec::shift_rows(&mut state);
         dec::mix_columns(&mut state);
+        key::add_round_key(&mut state, dec_round_keys, round);
     }
 
-    dec::shift_rows(&mut state);
     dec::sub_bytes(&mut state);
-    key::add_round_key(&mut state, &key_schedule, 0);
+    dec::shift_rows(&mut state);
+    key::add_round_key(&mut state, dec_round_keys, nr);
 
-    state::state_to_bytes(state)
-}
-
-fn get_nr(key_bits_len: usize) -> u8 {
-    match key_bits_len {
-        128 => 10,
-        192 => 12,
-        256 => 14,
-        _ => panic!("Invalid key length. Expected one of 128, 192, 256, got {key_bits_len}."),
-    }
+    let plaintext = state::state_to_bytes(state);
+    zeroize(&mut state);
+    plaintext
 }
 
 #[cfg(test)]
 #[rustfmt::skip]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
+    const BACKENDS: &[Backend] = &[
+        Backend::Reference,
+        Backend::TTable,
+        Backend::Bitsliced,
+        Backend::AesNi,
+    ];
+
+    // xorshift64, good enough to generate test inputs.
+    pub(crate) fn random_bytes(seed: &mut u64, output: &mut [u8]) {
+        for byte in output {
+            *seed ^= *seed << 13;
+            *seed ^= *seed >> 7;
+            *seed ^= *seed << 17;
+            *byte = *seed as u8;
+        }
+    }
+
+    fn assert_backend_matches_reference(
+        reference: &dyn BlockCipher,
+        cipher: &dyn BlockCipher,
+        seed: &mut u64,
+    ) {
+        for _ in 0..64 {
+            let mut block = [0u8; 16];
+            random_bytes(seed, &mut block);
+            let ciphertext = reference.encrypt_block(&block);
+            assert_eq!(ciphertext, cipher.encrypt_block(&block));
+            assert_eq!(reference.decrypt_block(&block), cipher.decrypt_block(&block));
+            assert_eq!(block, cipher.decrypt_block(&ciphertext));
+        }
+    }
+
     #[test]
     fn working_128() {
         let plaintext = &[
@@ -68,11 +401,14 @@ mod tests {
             0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4, 0xc5, 0x5a,
         ];
 
-        let ciphertext = encrypt_block(plaintext, key);
-        let recovered_plaintext = decrypt_block(&ciphertext, key);
+        for backend in BACKENDS {
+            let cipher = Aes128::with_backend(key, *backend);
+            let ciphertext = cipher.encrypt_block(plaintext);
+            let recovered_plaintext = cipher.decrypt_block(expected_ciphertext);
 
-        assert_eq!(&ciphertext, expected_ciphertext);
-        assert_eq!(&recovered_plaintext, plaintext);
+            assert_eq!(&ciphertext, expected_ciphertext);
+            assert_eq!(&recovered_plaintext, plaintext);
+        }
     }
 
     #[test]
@@ -88,11 +424,14 @@ mod tests {
             0xdd, 0xa9, 0x7c, 0xa4, 0x86, 0x4c, 0xdf, 0xe0, 0x6e, 0xaf, 0x70, 0xa0, 0xec, 0x0d, 0x71, 0x91,
         ];
 
-        let ciphertext = encrypt_block(plaintext, key);
-        let recovered_plaintext = decrypt_block(&ciphertext, key);
+        for backend in BACKENDS {
+            let cipher = Aes192::with_backend(key, *backend);
+            let ciphertext = cipher.encrypt_block(plaintext);
+            let recovered_plaintext = cipher.decrypt_block(expected_ciphertext);
 
-        assert_eq!(&ciphertext, expected_ciphertext);
-        assert_eq!(&recovered_plaintext, plaintext);
+            assert_eq!(&ciphertext, expected_ciphertext);
+            assert_eq!(&recovered_plaintext, plaintext);
+        }
     }
 
     #[test]
@@ -108,10 +447,73 @@ mod tests {
             0x8e, 0xa2, 0xb7, 0xca, 0x51, 0x67, 0x45, 0xbf, 0xea, 0xfc, 0x49, 0x90, 0x4b, 0x49, 0x60, 0x89,
         ];
 
-        let ciphertext = encrypt_block(plaintext, key);
-        let recovered_plaintext = decrypt_block(&ciphertext, key);
+        for backend in BACKENDS {
+            let cipher = Aes256::with_backend(key, *backend);
+            let ciphertext = cipher.encrypt_block(plaintext);
+            let recovered_plaintext = cipher.decrypt_block(expected_ciphertext);
+
+            assert_eq!(&ciphertext, expected_ciphertext);
+            assert_eq!(&recovered_plaintext, plaintext);
+        }
+    }
+
+    #[test]
+    fn keyed_ciphers_are_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Aes128>();
+        assert_send_sync::<Aes192>();
+        assert_send_sync::<Aes256>();
+    }
+
+    #[test]
+    fn backends_match_reference() {
+        let mut seed = 0x2545f4914f6cdd1d;
+        for backend in BACKENDS {
+            for _ in 0..16 {
+                let mut key = [0u8; 32];
+                random_bytes(&mut seed, &mut key);
+
+                let key_128 = key[..16].try_into().unwrap();
+                let reference = Aes128::with_backend(key_128, Backend::Reference);
+                let cipher = Aes128::with_backend(key_128, *backend);
+                assert_backend_matches_reference(&reference, &cipher, &mut seed);
+
+                let key_192 = key[..24].try_into().unwrap();
+                let reference = Aes192::with_backend(key_192, Backend::Reference);
+                let cipher = Aes192::with_backend(key_192, *backend);
+                assert_backend_matches_reference(&reference, &cipher, &mut seed);
+
+                let reference = Aes256::with_backend(&key, Backend::Reference);
+                let cipher = Aes256::with_backend(&key, *backend);
+                assert_backend_matches_reference(&reference, &cipher, &mut seed);
+            }
+        }
+    }
+
+    #[test]
+    fn blocks_match_single_block() {
+        let mut seed = 0x853c49e6748fea9b;
+        for backend in BACKENDS {
+            let mut key = [0u8; 32];
+            random_bytes(&mut seed, &mut key);
+            let cipher = Aes256::with_backend(&key, *backend);
+
+            // Lengths around the number of lanes of every backend.
+            for len in 0..=17 {
+                let mut plaintext = vec![[0u8; 16]; len];
+                for block in plaintext.iter_mut() {
+                    random_bytes(&mut seed, block);
+                }
+
+                let mut blocks = plaintext.clone();
+                cipher.encrypt_blocks(&mut blocks);
+                for (block, ciphertext) in plaintext.iter().zip(&blocks) {
+                    assert_eq!(cipher.encrypt_block(block), *ciphertext);
+                }
 
-        assert_eq!(&ciphertext, expected_ciphertext);
-        assert_eq!(&recovered_plaintext, plaintext);
+                cipher.decrypt_blocks(&mut blocks);
+                assert_eq!(plaintext, blocks);
+            }
+        }
     }
 }
@@ -0,0 +1,295 @@
+// Hardware implementation using AES-NI instructions of x86_64 processors.
+// https://www.intel.com/content/dam/doc/white-paper/advanced-encryption-standard-new-instructions-set-paper.pdf
+use core::arch::x86_64::{
+    __m128i, _mm_aesdec_si128, _mm_aesdeclast_si128, _mm_aesenc_si128, _mm_aesenclast_si128,
+    _mm_aesimc_si128, _mm_aeskeygenassist_si128, _mm_cvtsi128_si32, _mm_loadu_si128,
+    _mm_set1_epi32, _mm_setzero_si128, _mm_storeu_si128, _mm_xor_si128,
+};
+
+use crate::{zeroize::zeroize, Block, BLOCK_SIZE, NB};
+
+const MAX_NR: usize = 14;
+/// Number of blocks whose rounds are interleaved, hiding the latency of `aesenc` and `aesdec`.
+const LANES: usize = 8;
+
+#[cfg(feature = "std")]
+pub(crate) fn is_supported() -> bool {
+    is_x86_feature_detected!("aes") && is_x86_feature_detected!("sse2")
+}
+
+// Runtime detection needs std, otherwise the instructions have to be enabled for the target.
+#[cfg(not(feature = "std"))]
+pub(crate) fn is_supported() -> bool {
+    cfg!(all(target_feature = "aes", target_feature = "sse2"))
+}
+
+/// Round keys loaded into SSE registers.
+/// Decryption round keys have InvMixColumns applied, as required by `aesdec`.
+pub(crate) struct KeySchedule {
+    nr: u8,
+    round_keys: [__m128i; MAX_NR + 1],
+    dec_round_keys: [__m128i; MAX_NR + 1],
+}
+
+impl KeySchedule {
+    /// Returns `None` when the processor does not support AES-NI.
+    pub(crate) fn new(key: &[u8]) -> Option<Self> {
+        if !is_supported() {
+            return None;
+        }
+        // SAFETY: support of the required instructions was checked above.
+        Some(unsafe { key_expansion(key) })
+    }
+}
+
+impl Drop for KeySchedule {
+    fn drop(&mut self) {
+        zeroize(&mut self.round_keys);
+        zeroize(&mut self.dec_round_keys);
+    }
+}
+
+pub(crate) fn encrypt_block(plaintext: &Block, key_schedule: &KeySchedule) -> Block {
+    // SAFETY: KeySchedule can only be constructed when AES-NI is supported.
+    unsafe { encrypt(plaintext, key_schedule) }
+}
+
+pub(crate) fn decrypt_block(ciphertext: &Block, key_schedule: &KeySchedule) -> Block {
+    // SAFETY: KeySchedule can only be constructed when AES-NI is supported.
+    unsafe { decrypt(ciphertext, key_schedule) }
+}
+
+pub(crate) fn encrypt_blocks(blocks: &mut [Block], key_schedule: &KeySchedule) {
+    let mut chunks = blocks.chunks_exact_mut(LANES);
+    for chunk in &mut chunks {
+        // SAFETY: KeySchedule can only be constructed when AES-NI is supported.
+        unsafe { encrypt_lanes(chunk.try_into().unwrap(), key_schedule) }
+    }
+    for block in chunks.into_remainder() {
+        *block = encrypt_block(block, key_schedule);
+    }
+}
+
+pub(crate) fn decrypt_blocks(blocks: &mut [Block], key_schedule: &KeySchedule) {
+    let mut chunks = blocks.chunks_exact_mut(LANES);
+    for chunk in &mut chunks {
+        // SAFETY: KeySchedule can only be constructed when AES-NI is supported.
+        unsafe { decrypt_lanes(chunk.try_into().unwrap(), key_schedule) }
+    }
+    for block in chunks.into_remainder() {
+        *block = decrypt_block(block, key_schedule);
+    }
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn key_expansion(key: &[u8]) -> KeySchedule {
+    let nk = key.len() / 4;
+    let nr = nk + 6;
+    let len = NB * (nr + 1);
+
+    // Words are little endian, so RotWord is a rotation to the right.
+    let mut w = [0u32; NB * (MAX_NR + 1)];
+    for (word, key_word) in w.iter_mut().zip(key.chunks_exact(4)) {
+        *word = u32::from_le_bytes(key_word.try_into().unwrap());
+    }
+
+    let mut rcon = 1u32;
+    for i in nk..len {
+        let mut temp = w[i - 1];
+        if i % nk == 0 {
+            temp = sub_word(temp.rotate_right(8)) ^ rcon;
+            rcon = ((rcon << 1) ^ (0x1b * (rcon >> 7))) & 0xff;
+        } else if nk > 6 && i % nk == 4 {
+            temp = sub_word(temp);
+        }
+        w[i] = w[i - nk] ^ temp;
+    }
+
+    let mut round_keys = [_mm_setzero_si128(); MAX_NR + 1];
+    for (round_key, words) in round_keys.iter_mut().zip(w[..len].chunks_exact(NB)) {
+        let mut bytes = [0u8; BLOCK_SIZE];
+        for (bytes, word) in bytes.chunks_exact_mut(4).zip(words) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        *round_key = load(&bytes);
+        zeroize(&mut bytes);
+    }
+    zeroize(&mut w);
+
+    let mut dec_round_keys = [_mm_setzero_si128(); MAX_NR + 1];
+    dec_round_keys[0] = round_keys[nr];
+    for round in 1..nr {
+        dec_round_keys[round] = _mm_aesimc_si128(round_keys[nr - round]);
+    }
+    dec_round_keys[nr] = round_keys[0];
+
+    KeySchedule {
+        nr: nr as u8,
+        round_keys,
+        dec_round_keys,
+    }
+}
+
+// aeskeygenassist puts SubWord of the second word into the first one.
+#[target_feature(enable = "aes,sse2")]
+unsafe fn sub_word(word: u32) -> u32 {
+    let assist = _mm_aeskeygenassist_si128(_mm_set1_epi32(word as i32), 0);
+    _mm_cvtsi128_si32(assist) as u32
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn encrypt(plaintext: &Block, key_schedule: &KeySchedule) -> Block {
+    let nr = key_schedule.nr as usize;
+    let rk = &key_schedule.round_keys;
+
+    let mut state = _mm_xor_si128(load(plaintext), rk[0]);
+    for round_key in &rk[1..nr] {
+        state = _mm_aesenc_si128(state, *round_key);
+    }
+    state = _mm_aesenclast_si128(state, rk[nr]);
+
+    store(state)
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn decrypt(ciphertext: &Block, key_schedule: &KeySchedule) -> Block {
+    let nr = key_schedule.nr as usize;
+    let rk = &key_schedule.dec_round_keys;
+
+    let mut state = _mm_xor_si128(load(ciphertext), rk[0]);
+    for round_key in &rk[1..nr] {
+        state = _mm_aesdec_si128(state, *round_key);
+    }
+    state = _mm_aesdeclast_si128(state, rk[nr]);
+
+    store(state)
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn encrypt_lanes(blocks: &mut [Block; LANES], key_schedule: &KeySchedule) {
+    let nr = key_schedule.nr as usize;
+    let rk = &key_schedule.round_keys;
+
+    let mut state = [_mm_setzero_si128(); LANES];
+    for (state, block) in state.iter_mut().zip(blocks.iter()) {
+        *state = _mm_xor_si128(load(block), rk[0]);
+    }
+    for round_key in &rk[1..nr] {
+        for state in state.iter_mut() {
+            *state = _mm_aesenc_si128(*state, *round_key);
+        }
+    }
+    for (state, block) in state.iter().zip(blocks.iter_mut()) {
+        *block = store(_mm_aesenclast_si128(*state, rk[nr]));
+    }
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn decrypt_lanes(blocks: &mut [Block; LANES], key_schedule: &KeySchedule) {
+    let nr = key_schedule.nr as usize;
+    let rk = &key_schedule.dec_round_keys;
+
+    let mut state = [_mm_setzero_si128(); LANES];
+    for (state, block) in state.iter_mut().zip(blocks.iter()) {
+        *state = _mm_xor_si128(load(block), rk[0]);
+    }
+    for round_key in &rk[1..nr] {
+        for state in state.iter_mut() {
+            *state = _mm_aesdec_si128(*state, *round_key);
+        }
+    }
+    for (state, block) in state.iter().zip(blocks.iter_mut()) {
+        *block = store(_mm_aesdeclast_si128(*state, rk[nr]));
+    }
+}
+
+#[target_feature(enable = "sse2")]
+unsafe fn load(block: &Block) -> __m128i {
+    _mm_loadu_si128(block.as_ptr() as *const __m128i)
+}
+
+#[target_feature(enable = "sse2")]
+unsafe fn store(state: __m128i) -> Block {
+    let mut block = [0u8; BLOCK_SIZE];
+    _mm_storeu_si128(block.as_mut_ptr() as *mut __m128i, state);
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{aes::tests::random_bytes, aes::Backend, Aes128, Aes192, Aes256, BlockCipher};
+
+    fn assert_matches_reference(
+        reference: &dyn BlockCipher,
+        cipher: &dyn BlockCipher,
+        seed: &mut u64,
+    ) {
+        for _ in 0..256 {
+            let mut block = [0u8; BLOCK_SIZE];
+            random_bytes(seed, &mut block);
+            assert_eq!(
+                reference.encrypt_block(&block),
+                cipher.encrypt_block(&block)
+            );
+            assert_eq!(
+                reference.decrypt_block(&block),
+                cipher.decrypt_block(&block)
+            );
+        }
+    }
+
+    #[test]
+    fn key_expansion_matches_reference() {
+        if !is_supported() {
+            return;
+        }
+        let mut seed = 0x9e3779b97f4a7c15;
+        for key_len in [16, 24, 32] {
+            for _ in 0..64 {
+                let mut key = [0u8; 32];
+                random_bytes(&mut seed, &mut key);
+                let key = &key[..key_len];
+
+                let reference = crate::key::KeySchedule::new(key);
+                let key_schedule = KeySchedule::new(key).unwrap();
+                assert_eq!(reference.nr(), key_schedule.nr);
+                for (round_key, words) in key_schedule
+                    .round_keys
+                    .iter()
+                    .zip(reference.round_keys().chunks_exact(NB))
+                {
+                    // SAFETY: support was checked at the beginning of the test.
+                    assert_eq!(words.concat(), unsafe { store(*round_key) });
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn random_blocks_match_reference() {
+        if !is_supported() {
+            return;
+        }
+        let mut seed = 0xd1b54a32d192ed03;
+        for _ in 0..64 {
+            let mut key = [0u8; 32];
+            random_bytes(&mut seed, &mut key);
+
+            let key_128 = key[..16].try_into().unwrap();
+            let reference = Aes128::with_backend(key_128, Backend::Reference);
+            let cipher = Aes128::with_backend(key_128, Backend::AesNi);
+            assert_eq!(Backend::AesNi, cipher.backend());
+            assert_matches_reference(&reference, &cipher, &mut seed);
+
+            let key_192 = key[..24].try_into().unwrap();
+            let reference = Aes192::with_backend(key_192, Backend::Reference);
+            let cipher = Aes192::with_backend(key_192, Backend::AesNi);
+            assert_matches_reference(&reference, &cipher, &mut seed);
+
+            let reference = Aes256::with_backend(&key, Backend::Reference);
+            let cipher = Aes256::with_backend(&key, Backend::AesNi);
+            assert_matches_reference(&reference, &cipher, &mut seed);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+// PEM-like text container of a ciphertext and the parameters needed to decrypt it:
+//
+// -----BEGIN AES MESSAGE-----
+// Mode: AES/CBC/PKCS5Padding
+// IV: 24242424242424242424242424242424
+//
+// <base64 of the ciphertext, 64 characters per line>
+// -----END AES MESSAGE-----
+use alloc::{string::String, vec::Vec};
+use core::{fmt, str::FromStr};
+
+use crate::{
+    encoding::{Base64, Hex},
+    Block, Cipher, CipherError, Key, Mode, Operation, Padding, Transformation,
+};
+
+const BEGIN: &str = "-----BEGIN AES MESSAGE-----";
+const END: &str = "-----END AES MESSAGE-----";
+const LINE_LEN: usize = 64;
+
+fn malformed(reason: &'static str) -> CipherError {
+    CipherError::InvalidEncoding { reason }
+}
+
+/// Armored message, the ciphertext with `Mode` (a [`Transformation`]) and `IV` (hex,
+/// CBC only) headers. [`Display`](fmt::Display) writes the text, [`Armor::parse`] reads it
+/// back strictly, unknown or repeated headers and malformed base64 are rejected.
+pub struct Armor {
+    transformation: Transformation,
+    iv: Option<Block>,
+    ciphertext: Vec<u8>,
+}
+
+impl Armor {
+    /// Fails if `iv` doesn't fit the mode of the `transformation`, it has to be empty for ECB.
+    pub fn new(
+        transformation: Transformation,
+        iv: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Self, CipherError> {
+        let iv = match transformation.mode(iv)? {
+            Mode::Cbc(iv) => Some(iv),
+            _ => None,
+        };
+        Ok(Self {
+            transformation,
+            iv,
+            ciphertext: ciphertext.to_vec(),
+        })
+    }
+
+    pub fn transformation(&self) -> Transformation {
+        self.transformation
+    }
+
+    pub fn mode(&self) -> Mode {
+        match self.iv {
+            Some(iv) => Mode::Cbc(iv),
+            None => Mode::Ecb,
+        }
+    }
+
+    pub fn padding(&self) -> Padding {
+        self.transformation.padding()
+    }
+
+    pub fn ciphertext(&self) -> &[u8] {
+        &self.ciphertext
+    }
+
+    /// Cipher decrypting the message, see [`Transformation::cipher`].
+    pub fn decryptor(&self, key: &Key) -> Result<Cipher<'static>, CipherError> {
+        self.transformation.check_key(key)?;
+        Cipher::with_key(Operation::Decrypt, key, self.mode(), self.padding())
+    }
+
+    pub fn parse(text: &str) -> Result<Self, CipherError> {
+        let mut lines = text.trim_end().lines();
+        if lines.next() != Some(BEGIN) {
+            return Err(malformed("missing BEGIN AES MESSAGE line"));
+        }
+
+        let mut transformation = None;
+        let mut iv = None;
+        loop {
+            let line = lines
+                .next()
+                .ok_or(malformed("missing empty line after headers"))?;
+            if line.is_empty() {
+                break;
+            }
+            let (name, value) = line
+                .split_once(": ")
+                .ok_or(malformed("header is not `Name: value`"))?;
+            match name {
+                "Mode" if transformation.is_none() => {
+                    transformation = Some(Transformation::parse(value)?)
+                }
+                "IV" if iv.is_none() => iv = Some(Hex::decode(value)?),
+                _ => return Err(malformed("unknown or repeated header")),
+            }
+        }
+        let transformation = transformation.ok_or(malformed("missing Mode header"))?;
+
+        let mut body = String::new();
+        loop {
+            let line = lines
+                .next()
+                .ok_or(malformed("missing END AES MESSAGE line"))?;
+            if line == END {
+                break;
+            }
+            body.push_str(line);
+        }
+        if lines.next().is_some() {
+            return Err(malformed("text after END AES MESSAGE line"));
+        }
+
+        let ciphertext = Base64::Standard.decode(&body)?;
+        Self::new(
+            transformation,
+            iv.as_deref().unwrap_or_default(),
+            &ciphertext,
+        )
+    }
+}
+
+impl FromStr for Armor {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Armor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{BEGIN}")?;
+        writeln!(f, "Mode: {}", self.transformation)?;
+        if let Some(iv) = &self.iv {
+            writeln!(f, "IV: {}", Hex::Lower.encode(iv))?;
+        }
+        writeln!(f)?;
+        let body = Base64::Standard.encode(&self.ciphertext);
+        for line in body.as_bytes().chunks(LINE_LEN) {
+            writeln!(f, "{}", core::str::from_utf8(line).map_err(|_| fmt::Error)?)?;
+        }
+        writeln!(f, "{END}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_round_trip() {
+        let transformation = Transformation::parse("AES_128/CBC/PKCS5Padding").unwrap();
+        let armor = Armor::new(transformation, &[0xab; 16], &[7; 80]).unwrap();
+        let text = armor.to_string();
+        let expected_start = "-----BEGIN AES MESSAGE-----\n\
+            Mode: AES_128/CBC/PKCS5Padding\n\
+            IV: abababababababababababababababab\n\
+            \n";
+        assert!(text.starts_with(expected_start), "{text}");
+        assert!(text.ends_with("\n-----END AES MESSAGE-----\n"), "{text}");
+        // 80 bytes are 108 base64 characters, split into lines of 64.
+        assert_eq!(7, text.lines().count());
+
+        let parsed = Armor::parse(&text).unwrap();
+        assert_eq!(transformation, parsed.transformation());
+        assert!(matches!(parsed.mode(), Mode::Cbc(iv) if iv == [0xab; 16]));
+        assert_eq!(&[7; 80], parsed.ciphertext());
+
+        let ecb = Armor::new(Transformation::parse("AES").unwrap(), &[], &[]).unwrap();
+        let text = ecb.to_string();
+        assert_eq!(
+            "-----BEGIN AES MESSAGE-----\nMode: AES/ECB/PKCS5Padding\n\n-----END AES MESSAGE-----\n",
+            text
+        );
+        assert!(matches!(Armor::parse(&text).unwrap().mode(), Mode::Ecb));
+    }
+
+    #[test]
+    fn malformed_text_is_rejected() {
+        let valid = "-----BEGIN AES MESSAGE-----\nMode: AES/CBC/NoPadding\nIV: 00000000000000000000000000000000\n\nAAAAAAAAAAAAAAAAAAAAAA==\n-----END AES MESSAGE-----\n";
+        assert!(Armor::parse(valid).is_ok());
+        assert!(Armor::parse(&valid.replace('\n', "\r\n")).is_ok());
+
+        let cases = [
+            ("-----BEGIN AES MESSAGE-----\n", ""),
+            ("Mode: AES/CBC/NoPadding\n", ""),
+            (
+                "Mode: AES/CBC/NoPadding\n",
+                "Mode: AES/CBC/NoPadding\nMode: AES\n",
+            ),
+            ("IV: 00000000000000000000000000000000\n", "Iv: 00\n"),
+            ("\nAAAA", "AAAA"),
+            ("-----END AES MESSAGE-----\n", ""),
+            (
+                "-----END AES MESSAGE-----\n",
+                "-----END AES MESSAGE-----\ntrailing\n",
+            ),
+            ("AAAAAAAAAAAAAAAAAAAAAA==", "AAAAAAAAAAAAAAAAAAAAAA="),
+        ];
+        for (from, to) in cases {
+            let text = valid.replace(from, to);
+            assert!(
+                matches!(
+                    Armor::parse(&text),
+                    Err(CipherError::InvalidEncoding { .. })
+                ),
+                "{text}"
+            );
+        }
+
+        let short_iv = valid.replace("IV: 00000000000000000000000000000000", "IV: 0000");
+        assert_eq!(
+            Some(CipherError::InvalidIvLength { len: 2 }),
+            Armor::parse(&short_iv).err()
+        );
+        let unknown_mode = valid.replace("AES/CBC/NoPadding", "AES/GCM/NoPadding");
+        assert!(matches!(
+            Armor::parse(&unknown_mode),
+            Err(CipherError::InvalidTransformation { .. })
+        ));
+    }
+}
@@ -0,0 +1,524 @@
+// Constant-time bitsliced implementation processing four blocks at once.
+// Bit `i` of every byte of the four blocks is stored in `q[i]`, so the round function consists
+// only of bitwise operations and shifts, without any memory access depending on secret data.
+// The layout follows BearSSL's aes_ct64: https://www.bearssl.org/constanttime.html
+use crate::{zeroize::zeroize, Block, BLOCK_SIZE, NB};
+
+const MAX_NR: usize = 14;
+const BLOCKS: usize = 4;
+
+pub(crate) type State = [u64; 8];
+
+/// Bitsliced round keys, eight words per round.
+pub(crate) struct KeySchedule {
+    nr: u8,
+    round_keys: [u64; 8 * (MAX_NR + 1)],
+}
+
+impl KeySchedule {
+    /// Computes the key schedule using the bitsliced S-box, without table lookups.
+    pub(crate) fn new(key: &[u8]) -> Self {
+        let nk = key.len() / 4;
+        let nr = nk + 6;
+        let len = NB * (nr + 1);
+
+        let mut w = [0u32; NB * (MAX_NR + 1)];
+        for (word, key_word) in w.iter_mut().zip(key.chunks_exact(4)) {
+            *word = u32::from_le_bytes(key_word.try_into().unwrap());
+        }
+
+        let mut rcon = 1u32;
+        for i in nk..len {
+            let mut temp = w[i - 1];
+            if i % nk == 0 {
+                temp = sub_word(temp.rotate_right(8)) ^ rcon;
+                rcon = ((rcon << 1) ^ (0x1b * (rcon >> 7))) & 0xff;
+            } else if nk > 6 && i % nk == 4 {
+                temp = sub_word(temp);
+            }
+            w[i] = w[i - nk] ^ temp;
+        }
+
+        let mut round_keys = [0u64; 8 * (MAX_NR + 1)];
+        for round in 0..=nr {
+            let mut q = [0u64; 8];
+            let (q0, q4) = interleave_in(&w[round * NB..(round + 1) * NB]);
+            q[..4].fill(q0);
+            q[4..].fill(q4);
+            ortho(&mut q);
+            round_keys[round * 8..(round + 1) * 8].copy_from_slice(&q);
+            zeroize(&mut q);
+        }
+        zeroize(&mut w);
+
+        Self {
+            nr: nr as u8,
+            round_keys,
+        }
+    }
+
+    fn round_key(&self, round: usize) -> &[u64] {
+        &self.round_keys[round * 8..(round + 1) * 8]
+    }
+}
+
+impl Drop for KeySchedule {
+    fn drop(&mut self) {
+        zeroize(&mut self.round_keys);
+    }
+}
+
+pub(crate) fn encrypt_block(plaintext: &Block, key_schedule: &KeySchedule) -> Block {
+    let mut blocks = [
+        *plaintext,
+        [0; BLOCK_SIZE],
+        [0; BLOCK_SIZE],
+        [0; BLOCK_SIZE],
+    ];
+    encrypt_batch(&mut blocks, key_schedule);
+    let ciphertext = blocks[0];
+    zeroize(&mut blocks);
+    ciphertext
+}
+
+pub(crate) fn decrypt_block(ciphertext: &Block, key_schedule: &KeySchedule) -> Block {
+    let mut blocks = [
+        *ciphertext,
+        [0; BLOCK_SIZE],
+        [0; BLOCK_SIZE],
+        [0; BLOCK_SIZE],
+    ];
+    decrypt_batch(&mut blocks, key_schedule);
+    let plaintext = blocks[0];
+    zeroize(&mut blocks);
+    plaintext
+}
+
+pub(crate) fn encrypt_blocks(blocks: &mut [Block], key_schedule: &KeySchedule) {
+    let mut chunks = blocks.chunks_exact_mut(BLOCKS);
+    for chunk in &mut chunks {
+        encrypt_batch(chunk.try_into().unwrap(), key_schedule);
+    }
+    let remainder = chunks.into_remainder();
+    if !remainder.is_empty() {
+        let mut batch = [[0; BLOCK_SIZE]; BLOCKS];
+        batch[..remainder.len()].copy_from_slice(remainder);
+        encrypt_batch(&mut batch, key_schedule);
+        remainder.copy_from_slice(&batch[..remainder.len()]);
+        zeroize(&mut batch);
+    }
+}
+
+pub(crate) fn decrypt_blocks(blocks: &mut [Block], key_schedule: &KeySchedule) {
+    let mut chunks = blocks.chunks_exact_mut(BLOCKS);
+    for chunk in &mut chunks {
+        decrypt_batch(chunk.try_into().unwrap(), key_schedule);
+    }
+    let remainder = chunks.into_remainder();
+    if !remainder.is_empty() {
+        let mut batch = [[0; BLOCK_SIZE]; BLOCKS];
+        batch[..remainder.len()].copy_from_slice(remainder);
+        decrypt_batch(&mut batch, key_schedule);
+        remainder.copy_from_slice(&batch[..remainder.len()]);
+        zeroize(&mut batch);
+    }
+}
+
+fn encrypt_batch(blocks: &mut [Block; BLOCKS], key_schedule: &KeySchedule) {
+    let nr = key_schedule.nr as usize;
+    let mut q = load(blocks);
+
+    add_round_key(&mut q, key_schedule.round_key(0));
+    for round in 1..nr {
+        sub_bytes(&mut q);
+        shift_rows(&mut q);
+        mix_columns(&mut q);
+        add_round_key(&mut q, key_schedule.round_key(round));
+    }
+    sub_bytes(&mut q);
+    shift_rows(&mut q);
+    add_round_key(&mut q, key_schedule.round_key(nr));
+
+    store(&mut q, blocks);
+    zeroize(&mut q);
+}
+
+fn decrypt_batch(blocks: &mut [Block; BLOCKS], key_schedule: &KeySchedule) {
+    let nr = key_schedule.nr as usize;
+    let mut q = load(blocks);
+
+    add_round_key(&mut q, key_schedule.round_key(nr));
+    for round in (1..nr).rev() {
+        inv_shift_rows(&mut q);
+        inv_sub_bytes(&mut q);
+        add_round_key(&mut q, key_schedule.round_key(round));
+        inv_mix_columns(&mut q);
+    }
+    inv_shift_rows(&mut q);
+    inv_sub_bytes(&mut q);
+    add_round_key(&mut q, key_schedule.round_key(0));
+
+    store(&mut q, blocks);
+    zeroize(&mut q);
+}
+
+fn load(blocks: &[Block; BLOCKS]) -> State {
+    let mut q = [0u64; 8];
+    for (i, block) in blocks.iter().enumerate() {
+        let mut w = [0u32; NB];
+        for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_le_bytes(bytes.try_into().unwrap());
+        }
+        (q[i], q[i + 4]) = interleave_in(&w);
+    }
+    ortho(&mut q);
+    q
+}
+
+fn store(q: &mut State, blocks: &mut [Block; BLOCKS]) {
+    ortho(q);
+    for (i, block) in blocks.iter_mut().enumerate() {
+        let w = interleave_out(q[i], q[i + 4]);
+        for (bytes, word) in block.chunks_exact_mut(4).zip(w) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+    }
+}
+
+fn sub_word(word: u32) -> u32 {
+    let mut q = [0u64; 8];
+    q[0] = word as u64;
+    ortho(&mut q);
+    sub_bytes(&mut q);
+    ortho(&mut q);
+    q[0] as u32
+}
+
+// Spreads 4 words of a block, so that after ortho each byte ends up in its own column slot.
+fn interleave_in(w: &[u32]) -> (u64, u64) {
+    let mut x = [w[0] as u64, w[1] as u64, w[2] as u64, w[3] as u64];
+    for x in &mut x {
+        *x |= *x << 16;
+        *x &= 0x0000ffff0000ffff;
+        *x |= *x << 8;
+        *x &= 0x00ff00ff00ff00ff;
+    }
+    (x[0] | (x[2] << 8), x[1] | (x[3] << 8))
+}
+
+fn interleave_out(q0: u64, q1: u64) -> [u32; NB] {
+    let mut x = [
+        q0 & 0x00ff00ff00ff00ff,
+        q1 & 0x00ff00ff00ff00ff,
+        (q0 >> 8) & 0x00ff00ff00ff00ff,
+        (q1 >> 8) & 0x00ff00ff00ff00ff,
+    ];
+    for x in &mut x {
+        *x |= *x >> 8;
+        *x &= 0x0000ffff0000ffff;
+    }
+    x.map(|x| x as u32 | (x >> 16) as u32)
+}
+
+// Transposes 8x8 bit matrices, moving bit `i` of each byte into `q[i]`. It is an involution.
+fn ortho(q: &mut State) {
+    fn swap(q: &mut State, a: usize, b: usize, low: u64, shift: u32) {
+        let high = low << shift;
+        let (x, y) = (q[a], q[b]);
+        q[a] = (x & low) | ((y & low) << shift);
+        q[b] = ((x & high) >> shift) | (y & high);
+    }
+
+    for i in (0..8).step_by(2) {
+        swap(q, i, i + 1, 0x5555555555555555, 1);
+    }
+    for i in [0, 1, 4, 5] {
+        swap(q, i, i + 2, 0x3333333333333333, 2);
+    }
+    for i in 0..4 {
+        swap(q, i, i + 4, 0x0f0f0f0f0f0f0f0f, 4);
+    }
+}
+
+fn add_round_key(q: &mut State, round_key: &[u64]) {
+    for (q, k) in q.iter_mut().zip(round_key) {
+        *q ^= k;
+    }
+}
+
+// Boyar-Peralta S-box circuit: https://eprint.iacr.org/2009/191.pdf
+// Inputs and outputs are numbered from the most significant bit.
+fn sub_bytes(q: &mut State) {
+    let (x0, x1, x2, x3, x4, x5, x6, x7) = (q[7], q[6], q[5], q[4], q[3], q[2], q[1], q[0]);
+
+    // Top linear transformation.
+    let y14 = x3 ^ x5;
+    let y13 = x0 ^ x6;
+    let y9 = x0 ^ x3;
+    let y8 = x0 ^ x5;
+    let t0 = x1 ^ x2;
+    let y1 = t0 ^ x7;
+    let y4 = y1 ^ x3;
+    let y12 = y13 ^ y14;
+    let y2 = y1 ^ x0;
+    let y5 = y1 ^ x6;
+    let y3 = y5 ^ y8;
+    let t1 = x4 ^ y12;
+    let y15 = t1 ^ x5;
+    let y20 = t1 ^ x1;
+    let y6 = y15 ^ x7;
+    let y10 = y15 ^ t0;
+    let y11 = y20 ^ y9;
+    let y7 = x7 ^ y11;
+    let y17 = y10 ^ y11;
+    let y19 = y10 ^ y8;
+    let y16 = t0 ^ y11;
+    let y21 = y13 ^ y16;
+    let y18 = x0 ^ y16;
+
+    // Non-linear section.
+    let t2 = y12 & y15;
+    let t3 = y3 & y6;
+    let t4 = t3 ^ t2;
+    let t5 = y4 & x7;
+    let t6 = t5 ^ t2;
+    let t7 = y13 & y16;
+    let t8 = y5 & y1;
+    let t9 = t8 ^ t7;
+    let t10 = y2 & y7;
+    let t11 = t10 ^ t7;
+    let t12 = y9 & y11;
+    let t13 = y14 & y17;
+    let t14 = t13 ^ t12;
+    let t15 = y8 & y10;
+    let t16 = t15 ^ t12;
+    let t17 = t4 ^ t14;
+    let t18 = t6 ^ t16;
+    let t19 = t9 ^ t14;
+    let t20 = t11 ^ t16;
+    let t21 = t17 ^ y20;
+    let t22 = t18 ^ y19;
+    let t23 = t19 ^ y21;
+    let t24 = t20 ^ y18;
+
+    let t25 = t21 ^ t22;
+    let t26 = t21 & t23;
+    let t27 = t24 ^ t26;
+    let t28 = t25 & t27;
+    let t29 = t28 ^ t22;
+    let t30 = t23 ^ t24;
+    let t31 = t22 ^ t26;
+    let t32 = t31 & t30;
+    let t33 = t32 ^ t24;
+    let t34 = t23 ^ t33;
+    let t35 = t27 ^ t33;
+    let t36 = t24 & t35;
+    let t37 = t36 ^ t34;
+    let t38 = t27 ^ t36;
+    let t39 = t29 & t38;
+    let t40 = t25 ^ t39;
+
+    let t41 = t40 ^ t37;
+    let t42 = t29 ^ t33;
+    let t43 = t29 ^ t40;
+    let t44 = t33 ^ t37;
+    let t45 = t42 ^ t41;
+    let z0 = t44 & y15;
+    let z1 = t37 & y6;
+    let z2 = t33 & x7;
+    let z3 = t43 & y16;
+    let z4 = t40 & y1;
+    let z5 = t29 & y7;
+    let z6 = t42 & y11;
+    let z7 = t45 & y17;
+    let z8 = t41 & y10;
+    let z9 = t44 & y12;
+    let z10 = t37 & y3;
+    let z11 = t33 & y4;
+    let z12 = t43 & y13;
+    let z13 = t40 & y5;
+    let z14 = t29 & y2;
+    let z15 = t42 & y9;
+    let z16 = t45 & y14;
+    let z17 = t41 & y8;
+
+    // Bottom linear transformation.
+    let t46 = z15 ^ z16;
+    let t47 = z10 ^ z11;
+    let t48 = z5 ^ z13;
+    let t49 = z9 ^ z10;
+    let t50 = z2 ^ z12;
+    let t51 = z2 ^ z5;
+    let t52 = z7 ^ z8;
+    let t53 = z0 ^ z3;
+    let t54 = z6 ^ z7;
+    let t55 = z16 ^ z17;
+    let t56 = z12 ^ t48;
+    let t57 = t50 ^ t53;
+    let t58 = z4 ^ t46;
+    let t59 = z3 ^ t54;
+    let t60 = t46 ^ t57;
+    let t61 = z14 ^ t57;
+    let t62 = t52 ^ t58;
+    let t63 = t49 ^ t58;
+    let t64 = z4 ^ t59;
+    let t65 = t61 ^ t62;
+    let t66 = z1 ^ t63;
+    let s0 = t59 ^ t63;
+    let s6 = t56 ^ !t62;
+    let s7 = t48 ^ !t60;
+    let t67 = t64 ^ t65;
+    let s3 = t53 ^ t66;
+    let s4 = t51 ^ t66;
+    let s5 = t47 ^ t65;
+    let s1 = t64 ^ !s3;
+    let s2 = t55 ^ !t67;
+
+    *q = [s7, s6, s5, s4, s3, s2, s1, s0];
+}
+
+// InvSubBytes(x) = A'(SubBytes(A'(x))), where A'(x) = A^-1(x ^ 0x63) reverts the affine
+// transformation of the S-box, leaving only the multiplicative inverse in GF(2^8).
+fn inv_sub_bytes(q: &mut State) {
+    inv_affine(q);
+    sub_bytes(q);
+    inv_affine(q);
+}
+
+fn inv_affine(q: &mut State) {
+    let (q0, q1, q2, q3, q4, q5, q6, q7) = (!q[0], !q[1], q[2], q[3], q[4], !q[5], !q[6], q[7]);
+    *q = [
+        q2 ^ q5 ^ q7,
+        q3 ^ q6 ^ q0,
+        q4 ^ q7 ^ q1,
+        q5 ^ q0 ^ q2,
+        q6 ^ q1 ^ q3,
+        q7 ^ q2 ^ q4,
+        q0 ^ q3 ^ q5,
+        q1 ^ q4 ^ q6,
+    ];
+}
+
+// Every row takes 16 bits, 4 bits per column (one per block).
+fn shift_rows(q: &mut State) {
+    for x in q {
+        *x = (*x & 0x000000000000ffff)
+            | ((*x & 0x00000000fff00000) >> 4)
+            | ((*x & 0x00000000000f0000) << 12)
+            | ((*x & 0x0000ff0000000000) >> 8)
+            | ((*x & 0x000000ff00000000) << 8)
+            | ((*x & 0xf000000000000000) >> 12)
+            | ((*x & 0x0fff000000000000) << 4);
+    }
+}
+
+fn inv_shift_rows(q: &mut State) {
+    for x in q {
+        *x = (*x & 0x000000000000ffff)
+            | ((*x & 0x000000000fff0000) << 4)
+            | ((*x & 0x00000000f0000000) >> 12)
+            | ((*x & 0x0000ff0000000000) >> 8)
+            | ((*x & 0x000000ff00000000) << 8)
+            | ((*x & 0x000f000000000000) << 12)
+            | ((*x & 0xfff0000000000000) >> 4);
+    }
+}
+
+// Rotates rows by one (r) and by two (rotr32) to compute
+// 2•a[i] ^ 3•a[i + 1] ^ a[i + 2] ^ a[i + 3] = 2•(a[i] ^ a[i + 1]) ^ a[i + 1] ^ a[i + 2] ^ a[i + 3]
+fn mix_columns(q: &mut State) {
+    let [q0, q1, q2, q3, q4, q5, q6, q7] = *q;
+    let [r0, r1, r2, r3, r4, r5, r6, r7] = q.map(|x| x.rotate_right(16));
+
+    *q = [
+        q7 ^ r7 ^ r0 ^ rotr32(q0 ^ r0),
+        q0 ^ r0 ^ q7 ^ r7 ^ r1 ^ rotr32(q1 ^ r1),
+        q1 ^ r1 ^ r2 ^ rotr32(q2 ^ r2),
+        q2 ^ r2 ^ q7 ^ r7 ^ r3 ^ rotr32(q3 ^ r3),
+        q3 ^ r3 ^ q7 ^ r7 ^ r4 ^ rotr32(q4 ^ r4),
+        q4 ^ r4 ^ r5 ^ rotr32(q5 ^ r5),
+        q5 ^ r5 ^ r6 ^ rotr32(q6 ^ r6),
+        q6 ^ r6 ^ r7 ^ rotr32(q7 ^ r7),
+    ];
+}
+
+// InvMixColumns = MixColumns • circ(5, 0, 4, 0), so a[i] ^= 4•(a[i] ^ a[i + 2]) comes first.
+fn inv_mix_columns(q: &mut State) {
+    let t = xtime(xtime(q.map(|x| x ^ rotr32(x))));
+    for (q, t) in q.iter_mut().zip(t) {
+        *q ^= t;
+    }
+    mix_columns(q);
+}
+
+// Multiplication by x in GF(2^8) of every bitsliced byte.
+fn xtime(q: State) -> State {
+    let [q0, q1, q2, q3, q4, q5, q6, q7] = q;
+    [q7, q0 ^ q7, q1, q2 ^ q7, q3 ^ q7, q4, q5, q6]
+}
+
+fn rotr32(x: u64) -> u64 {
+    x.rotate_right(32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lookup::{INV_S_BOX, S_BOX};
+
+    #[test]
+    fn sub_bytes_matches_s_box() {
+        for x in 0..=255u8 {
+            let word = u32::from_le_bytes([x, x, x, x]);
+            let expected = u32::from_le_bytes([S_BOX[x as usize]; 4]);
+            assert_eq!(expected, sub_word(word));
+        }
+    }
+
+    #[test]
+    fn inv_sub_bytes_matches_inv_s_box() {
+        for x in 0..=255u8 {
+            let mut q = [0u64; 8];
+            q[0] = u32::from_le_bytes([x; 4]) as u64;
+            ortho(&mut q);
+            inv_sub_bytes(&mut q);
+            ortho(&mut q);
+            assert_eq!(u32::from_le_bytes([INV_S_BOX[x as usize]; 4]), q[0] as u32);
+        }
+    }
+
+    #[test]
+    fn ortho_is_involution() {
+        let before = [
+            0x0123456789abcdef,
+            0xfedcba9876543210,
+            0x0f1e2d3c4b5a6978,
+            0x8796a5b4c3d2e1f0,
+            0x1111111111111111,
+            0x2222222222222222,
+            0xdeadbeefdeadbeef,
+            0x0000000000000001,
+        ];
+        let mut q = before;
+        ortho(&mut q);
+        assert_ne!(before, q);
+        ortho(&mut q);
+        assert_eq!(before, q);
+    }
+
+    #[test]
+    fn blocks_are_independent() {
+        let key = [0x2b; 16];
+        let key_schedule = KeySchedule::new(&key);
+        let mut blocks = [[0x00; 16], [0x11; 16], [0x22; 16], [0x33; 16]];
+        let plaintext = blocks;
+
+        encrypt_batch(&mut blocks, &key_schedule);
+        for (block, ciphertext) in plaintext.iter().zip(blocks) {
+            assert_eq!(encrypt_block(block, &key_schedule), ciphertext);
+        }
+
+        decrypt_batch(&mut blocks, &key_schedule);
+        assert_eq!(plaintext, blocks);
+    }
+}
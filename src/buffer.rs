@@ -0,0 +1,132 @@
+use crate::{zeroize::zeroize, BLOCK_SIZE};
+
+/// Bytes carried over between updates, never more than one block.
+pub(crate) struct Buffer {
+    bytes: [u8; BLOCK_SIZE],
+    len: usize,
+}
+
+impl Buffer {
+    pub(crate) fn new() -> Self {
+        Self {
+            bytes: [0u8; BLOCK_SIZE],
+            len: 0,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn as_slice(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    pub(crate) fn clear(&mut self) {
+        zeroize(&mut self.bytes);
+        self.len = 0;
+    }
+
+    /// Replaces buffered bytes with `bytes`, e.g. restored from a checkpoint.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn fill_from(&mut self, bytes: &[u8]) -> Result<(), crate::CipherError> {
+        if bytes.len() > BLOCK_SIZE {
+            return Err(crate::CipherError::InvalidState);
+        }
+        self.clear();
+        self.bytes[..bytes.len()].copy_from_slice(bytes);
+        self.len = bytes.len();
+        Ok(())
+    }
+
+    /// Writes buffered bytes followed by `input` to `output[..output_len]`
+    /// and keeps the rest of the input in the buffer.
+    pub(crate) fn drain_into(&mut self, input: &[u8], output: &mut [u8], output_len: usize) {
+        if output_len == 0 {
+            self.bytes[self.len..(self.len + input.len())].copy_from_slice(input);
+            self.len += input.len();
+            return;
+        }
+
+        let consumed = output_len - self.len;
+        output[..self.len].copy_from_slice(self.as_slice());
+        output[self.len..output_len].copy_from_slice(&input[..consumed]);
+
+        let leftovers = &input[consumed..];
+        self.bytes[..leftovers.len()].copy_from_slice(leftovers);
+        self.len = leftovers.len();
+    }
+
+    /// Same as `drain_into`, but `data` is used as the output. Buffered bytes are moved
+    /// to the front of it, so `output_len` can't exceed `data.len()`.
+    pub(crate) fn drain_in_place(&mut self, data: &mut [u8], output_len: usize) {
+        if output_len == 0 {
+            let input_len = data.len();
+            self.bytes[self.len..(self.len + input_len)].copy_from_slice(data);
+            self.len += input_len;
+            return;
+        }
+
+        let consumed = output_len - self.len;
+        let mut leftovers = [0u8; BLOCK_SIZE];
+        let leftovers_len = data.len() - consumed;
+        leftovers[..leftovers_len].copy_from_slice(&data[consumed..]);
+
+        data.copy_within(..consumed, self.len);
+        data[..self.len].copy_from_slice(&self.bytes[..self.len]);
+
+        self.bytes = leftovers;
+        self.len = leftovers_len;
+        zeroize(&mut leftovers);
+    }
+}
+
+impl Drop for Buffer {
+    fn drop(&mut self) {
+        zeroize(&mut self.bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drain_into_keeps_leftovers() {
+        let mut buffer = Buffer::new();
+        let mut output = [0u8; 32];
+
+        buffer.drain_into(&[1, 2, 3], &mut output, 0);
+        assert_eq!(&[1, 2, 3], buffer.as_slice());
+
+        let input: Vec<u8> = (4..=20).collect();
+        buffer.drain_into(&input, &mut output, 16);
+        assert_eq!((1..=16).collect::<Vec<u8>>(), output[..16]);
+        assert_eq!(&[17, 18, 19, 20], buffer.as_slice());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn fill_from_replaces_bytes() {
+        let mut buffer = Buffer::new();
+        buffer.fill_from(&[1, 2, 3]).unwrap();
+        buffer.fill_from(&[4]).unwrap();
+        assert_eq!(&[4], buffer.as_slice());
+        assert_eq!(
+            Err(crate::CipherError::InvalidState),
+            buffer.fill_from(&[0; 17])
+        );
+        assert_eq!(&[4], buffer.as_slice());
+    }
+
+    #[test]
+    fn drain_in_place_keeps_leftovers() {
+        let mut buffer = Buffer::new();
+        buffer.drain_into(&[1, 2, 3], &mut [], 0);
+
+        let mut data: Vec<u8> = (4..=35).collect();
+        buffer.drain_in_place(&mut data, 32);
+        assert_eq!((1..=32).collect::<Vec<u8>>(), data);
+        assert_eq!(&[33, 34, 35], buffer.as_slice());
+    }
+}
@@ -0,0 +1,216 @@
+// Serialized state of a cipher in the middle of a stream.
+//
+// Layout of version 1, all lengths are single bytes:
+// magic "AESC" | version | operation | mode | padding | key ID length | key ID
+// | IV (CBC only) | buffered bytes length | buffered bytes
+use alloc::vec::Vec;
+
+use crate::{zeroize::zeroize, Block, CipherError, Operation, BLOCK_SIZE};
+
+const MAGIC: &[u8; 4] = b"AESC";
+const VERSION: u8 = 1;
+
+const ENCRYPT: u8 = 0;
+const DECRYPT: u8 = 1;
+const ECB: u8 = 0;
+const CBC: u8 = 1;
+const PKCS7: u8 = 0;
+const NO_PADDING: u8 = 1;
+
+/// State of a [`Cipher`](crate::Cipher) in the middle of a stream, created with
+/// [`Cipher::checkpoint`](crate::Cipher::checkpoint) and continued with
+/// [`Cipher::resume`](crate::Cipher::resume).
+///
+/// Key is not included, only an ID chosen by the application, which is used to find
+/// the key again on resume. Buffered bytes of the encryption are plaintext, so the
+/// serialized state has to be protected like the data itself.
+pub struct Checkpoint {
+    pub(crate) operation: Operation,
+    pub(crate) iv: Option<Block>,
+    /// PKCS7 if set, otherwise no padding.
+    pub(crate) padded: bool,
+    pub(crate) buffer: [u8; BLOCK_SIZE],
+    pub(crate) buffer_len: usize,
+    key_id: Vec<u8>,
+}
+
+impl Checkpoint {
+    pub(crate) fn new(
+        operation: Operation,
+        iv: Option<Block>,
+        padded: bool,
+        buffered: &[u8],
+        key_id: &[u8],
+    ) -> Result<Self, CipherError> {
+        if key_id.len() > u8::MAX as usize {
+            return Err(CipherError::InvalidState);
+        }
+        let mut buffer = [0u8; BLOCK_SIZE];
+        buffer[..buffered.len()].copy_from_slice(buffered);
+        Ok(Self {
+            operation,
+            iv,
+            padded,
+            buffer,
+            buffer_len: buffered.len(),
+            key_id: key_id.to_vec(),
+        })
+    }
+
+    /// ID of the key passed to [`Cipher::checkpoint`](crate::Cipher::checkpoint).
+    pub fn key_id(&self) -> &[u8] {
+        &self.key_id
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + self.key_id.len() + 2 * BLOCK_SIZE + 1);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(match self.operation {
+            Operation::Encrypt => ENCRYPT,
+            Operation::Decrypt => DECRYPT,
+        });
+        bytes.push(if self.iv.is_some() { CBC } else { ECB });
+        bytes.push(if self.padded { PKCS7 } else { NO_PADDING });
+        bytes.push(self.key_id.len() as u8);
+        bytes.extend_from_slice(&self.key_id);
+        if let Some(iv) = &self.iv {
+            bytes.extend_from_slice(iv);
+        }
+        bytes.push(self.buffer_len as u8);
+        bytes.extend_from_slice(&self.buffer[..self.buffer_len]);
+        bytes
+    }
+
+    /// Parses state serialized with [`Checkpoint::to_bytes`]. Malformed input or unknown
+    /// version is reported as [`CipherError::InvalidState`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CipherError> {
+        let mut reader = Reader { bytes };
+        if reader.take(MAGIC.len())? != MAGIC || reader.byte()? != VERSION {
+            return Err(CipherError::InvalidState);
+        }
+        let operation = match reader.byte()? {
+            ENCRYPT => Operation::Encrypt,
+            DECRYPT => Operation::Decrypt,
+            _ => return Err(CipherError::InvalidState),
+        };
+        let mode = reader.byte()?;
+        let padded = match reader.byte()? {
+            PKCS7 => true,
+            NO_PADDING => false,
+            _ => return Err(CipherError::InvalidState),
+        };
+        let key_id_len = reader.byte()? as usize;
+        let key_id = reader.take(key_id_len)?;
+        let iv = match mode {
+            ECB => None,
+            CBC => Some(reader.take(BLOCK_SIZE)?.try_into().unwrap()),
+            _ => return Err(CipherError::InvalidState),
+        };
+        // Encryption buffers only incomplete blocks, decryption holds back a whole one
+        // for unpadding.
+        let buffer_len = reader.byte()? as usize;
+        let max_buffer_len = match operation {
+            Operation::Decrypt if padded => BLOCK_SIZE,
+            _ => BLOCK_SIZE - 1,
+        };
+        if buffer_len > max_buffer_len {
+            return Err(CipherError::InvalidState);
+        }
+        let buffered = reader.take(buffer_len)?;
+        if !reader.bytes.is_empty() {
+            return Err(CipherError::InvalidState);
+        }
+        Self::new(operation, iv, padded, buffered, key_id)
+    }
+}
+
+impl Drop for Checkpoint {
+    fn drop(&mut self) {
+        zeroize(&mut self.buffer);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], CipherError> {
+        if self.bytes.len() < len {
+            return Err(CipherError::InvalidState);
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, CipherError> {
+        Ok(self.take(1)?[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes_round_trip() {
+        let checkpoint =
+            Checkpoint::new(Operation::Decrypt, Some([7; 16]), true, &[1; 16], b"key-1").unwrap();
+        let bytes = checkpoint.to_bytes();
+        assert_eq!(b"AESC\x01\x01\x01\x00\x05key-1", &bytes[..14]);
+
+        let parsed = Checkpoint::from_bytes(&bytes).unwrap();
+        assert_eq!(Operation::Decrypt, parsed.operation);
+        assert_eq!(Some([7; 16]), parsed.iv);
+        assert!(parsed.padded);
+        assert_eq!([1; 16], parsed.buffer[..parsed.buffer_len]);
+        assert_eq!(b"key-1", parsed.key_id());
+    }
+
+    #[test]
+    fn malformed_bytes_are_rejected() {
+        let bytes = Checkpoint::new(Operation::Encrypt, None, true, &[1; 3], b"id")
+            .unwrap()
+            .to_bytes();
+        assert!(Checkpoint::from_bytes(&bytes).is_ok());
+
+        for len in 0..bytes.len() {
+            assert!(Checkpoint::from_bytes(&bytes[..len]).is_err());
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(Checkpoint::from_bytes(&trailing).is_err());
+
+        let mut version = bytes.clone();
+        version[4] = 2;
+        assert!(Checkpoint::from_bytes(&version).is_err());
+
+        // Encryption never buffers a whole block.
+        let mut full_buffer = bytes[..bytes.len() - 4].to_vec();
+        full_buffer.push(16);
+        full_buffer.extend_from_slice(&[0; 16]);
+        assert_eq!(
+            Err(CipherError::InvalidState),
+            Checkpoint::from_bytes(&full_buffer).map(|_| ())
+        );
+
+        let mut padding = bytes.clone();
+        padding[7] = 2;
+        assert!(Checkpoint::from_bytes(&padding).is_err());
+    }
+
+    #[test]
+    fn unpadded_decryption_holds_back_no_block() {
+        let checkpoint = Checkpoint::new(Operation::Decrypt, None, false, &[], b"id").unwrap();
+        let bytes = checkpoint.to_bytes();
+        assert_eq!(NO_PADDING, bytes[7]);
+        assert!(!Checkpoint::from_bytes(&bytes).unwrap().padded);
+
+        let mut full_buffer = bytes[..bytes.len() - 1].to_vec();
+        full_buffer.push(16);
+        full_buffer.extend_from_slice(&[0; 16]);
+        assert!(Checkpoint::from_bytes(&full_buffer).is_err());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     lookup::{INV_S_BOX, MULT_11_LOOKUP, MULT_13_LOOKUP, MULT_14_LOOKUP, MULT_9_LOOKUP},
-    Block, NB,
+    Block, Word, NB,
 };
 
 pub(crate) fn sub_bytes(state: &mut Block) {
@@ -15,8 +15,7 @@ pub(crate) fn sub_bytes(state: &mut Block) {
 
 #[allow(clippy::identity_op)] // for readability
 pub(crate) fn shift_rows(state: &mut Block) {
-    // todo: mem swap
-    let state_clone = state.to_vec();
+    let state_clone = *state;
     state[1 * 4 + 0] = state_clone[1 * 4 + 3];
     state[1 * 4 + 1] = state_clone[1 * 4 + 0];
     state[1 * 4 + 2] = state_clone[1 * 4 + 1];
@@ -39,23 +38,31 @@ pub(crate) fn mix_columns(state: &mut Block) {
     }
 }
 
-// https://en.wikipedia.org/wiki/Rijndael_MixColumns#Matrix_representation
 #[allow(clippy::identity_op, clippy::erasing_op)] // for readability
 fn mix_column(state: &mut Block, col: usize) {
-    let (b0, b1, b2, b3) = (
-        state[0 * 4 + col] as usize,
-        state[1 * 4 + col] as usize,
-        state[2 * 4 + col] as usize,
-        state[3 * 4 + col] as usize,
-    );
-    state[0 * 4 + col] =
-        MULT_14_LOOKUP[b0] ^ MULT_11_LOOKUP[b1] ^ MULT_13_LOOKUP[b2] ^ MULT_9_LOOKUP[b3];
-    state[1 * 4 + col] =
-        MULT_9_LOOKUP[b0] ^ MULT_14_LOOKUP[b1] ^ MULT_11_LOOKUP[b2] ^ MULT_13_LOOKUP[b3];
-    state[2 * 4 + col] =
-        MULT_13_LOOKUP[b0] ^ MULT_9_LOOKUP[b1] ^ MULT_14_LOOKUP[b2] ^ MULT_11_LOOKUP[b3];
-    state[3 * 4 + col] =
-        MULT_11_LOOKUP[b0] ^ MULT_13_LOOKUP[b1] ^ MULT_9_LOOKUP[b2] ^ MULT_14_LOOKUP[b3];
+    let column = [
+        state[0 * 4 + col],
+        state[1 * 4 + col],
+        state[2 * 4 + col],
+        state[3 * 4 + col],
+    ];
+    let column = mix_word(&column);
+    state[0 * 4 + col] = column[0];
+    state[1 * 4 + col] = column[1];
+    state[2 * 4 + col] = column[2];
+    state[3 * 4 + col] = column[3];
+}
+
+/// InvMixColumns of a single column, also applied to the decryption round keys.
+// https://en.wikipedia.org/wiki/Rijndael_MixColumns#Matrix_representation
+pub(crate) fn mix_word(column: &Word) -> Word {
+    let [b0, b1, b2, b3] = column.map(usize::from);
+    [
+        MULT_14_LOOKUP[b0] ^ MULT_11_LOOKUP[b1] ^ MULT_13_LOOKUP[b2] ^ MULT_9_LOOKUP[b3],
+        MULT_9_LOOKUP[b0] ^ MULT_14_LOOKUP[b1] ^ MULT_11_LOOKUP[b2] ^ MULT_13_LOOKUP[b3],
+        MULT_13_LOOKUP[b0] ^ MULT_9_LOOKUP[b1] ^ MULT_14_LOOKUP[b2] ^ MULT_11_LOOKUP[b3],
+        MULT_11_LOOKUP[b0] ^ MULT_13_LOOKUP[b1] ^ MULT_9_LOOKUP[b2] ^ MULT_14_LOOKUP[b3],
+    ]
 }
 
 #[cfg(test)]
@@ -15,8 +15,7 @@ pub(crate) fn sub_bytes(state: &mut Block) {
 
 #[allow(clippy::identity_op)] // for readability
 pub(crate) fn shift_rows(state: &mut Block) {
-    // todo: mem swap
-    let state_clone = state.to_vec();
+    let state_clone = *state;
     state[1 * 4 + 0] = state_clone[1 * 4 + 1];
     state[1 * 4 + 1] = state_clone[1 * 4 + 2];
     state[1 * 4 + 2] = state_clone[1 * 4 + 3];
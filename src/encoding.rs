@@ -0,0 +1,239 @@
+// Text encodings of keys, IVs and ciphertexts.
+use alloc::{string::String, vec::Vec};
+
+use crate::CipherError;
+
+const HEX_LOWER: &[u8; 16] = b"0123456789abcdef";
+const HEX_UPPER: &[u8; 16] = b"0123456789ABCDEF";
+
+const BASE64_STANDARD: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL_SAFE: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Hexadecimal encoding, two digits per byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hex {
+    Lower,
+    Upper,
+}
+
+impl Hex {
+    pub fn encode(self, bytes: &[u8]) -> String {
+        let digits = match self {
+            Hex::Lower => HEX_LOWER,
+            Hex::Upper => HEX_UPPER,
+        };
+        let mut text = String::with_capacity(2 * bytes.len());
+        for byte in bytes {
+            text.push(digits[(byte >> 4) as usize] as char);
+            text.push(digits[(byte & 0x0f) as usize] as char);
+        }
+        text
+    }
+
+    /// Decodes digits of either case, anything else including whitespace is rejected.
+    pub fn decode(text: &str) -> Result<Vec<u8>, CipherError> {
+        if text.len() % 2 != 0 {
+            return Err(CipherError::InvalidEncoding {
+                reason: "hex length is odd",
+            });
+        }
+        text.as_bytes()
+            .chunks(2)
+            .map(|pair| Ok(hex_digit(pair[0])? << 4 | hex_digit(pair[1])?))
+            .collect()
+    }
+}
+
+fn hex_digit(digit: u8) -> Result<u8, CipherError> {
+    match digit {
+        b'0'..=b'9' => Ok(digit - b'0'),
+        b'a'..=b'f' => Ok(digit - b'a' + 10),
+        b'A'..=b'F' => Ok(digit - b'A' + 10),
+        _ => Err(CipherError::InvalidEncoding {
+            reason: "invalid hex digit",
+        }),
+    }
+}
+
+/// Base64 encoding (RFC 4648) with the standard or URL and file name safe alphabet,
+/// with or without `=` padding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Base64 {
+    Standard,
+    StandardUnpadded,
+    UrlSafe,
+    UrlSafeUnpadded,
+}
+
+impl Base64 {
+    fn alphabet(self) -> &'static [u8; 64] {
+        match self {
+            Base64::Standard | Base64::StandardUnpadded => BASE64_STANDARD,
+            Base64::UrlSafe | Base64::UrlSafeUnpadded => BASE64_URL_SAFE,
+        }
+    }
+
+    fn padded(self) -> bool {
+        matches!(self, Base64::Standard | Base64::UrlSafe)
+    }
+
+    pub fn encode(self, bytes: &[u8]) -> String {
+        let alphabet = self.alphabet();
+        let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+        for chunk in bytes.chunks(3) {
+            let mut group = [0u8; 3];
+            group[..chunk.len()].copy_from_slice(chunk);
+            let bits = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+            // n bytes are covered by n + 1 characters.
+            for i in 0..=chunk.len() {
+                text.push(alphabet[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            }
+            if self.padded() {
+                for _ in chunk.len()..3 {
+                    text.push('=');
+                }
+            }
+        }
+        text
+    }
+
+    /// Decodes text of this exact variant. Characters of the other alphabet, whitespace,
+    /// missing or unexpected padding and non-zero trailing bits are rejected.
+    pub fn decode(self, text: &str) -> Result<Vec<u8>, CipherError> {
+        let mut text = text.as_bytes();
+        if self.padded() {
+            if text.len() % 4 != 0 {
+                return Err(CipherError::InvalidEncoding {
+                    reason: "base64 length is not a multiple of 4",
+                });
+            }
+            let padding = text
+                .iter()
+                .rev()
+                .take(2)
+                .take_while(|&&c| c == b'=')
+                .count();
+            text = &text[..text.len() - padding];
+        }
+        if text.len() % 4 == 1 {
+            return Err(CipherError::InvalidEncoding {
+                reason: "base64 length is invalid",
+            });
+        }
+
+        let alphabet = self.alphabet();
+        let mut bytes = Vec::with_capacity(text.len() / 4 * 3 + 2);
+        for chunk in text.chunks(4) {
+            let mut bits = 0u32;
+            for (i, &c) in chunk.iter().enumerate() {
+                let value =
+                    alphabet
+                        .iter()
+                        .position(|&a| a == c)
+                        .ok_or(CipherError::InvalidEncoding {
+                            reason: "invalid base64 character",
+                        })?;
+                bits |= (value as u32) << (18 - 6 * i);
+            }
+            // n + 1 characters carry n bytes, remaining bits have to be zero.
+            let len = chunk.len() - 1;
+            if bits & (0x00ff_ffff >> (8 * len)) != 0 {
+                return Err(CipherError::InvalidEncoding {
+                    reason: "base64 trailing bits are not zero",
+                });
+            }
+            bytes.extend_from_slice(&bits.to_be_bytes()[1..=len]);
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_round_trip() {
+        let bytes = [0x00, 0x1f, 0xa0, 0xff];
+        assert_eq!("001fa0ff", Hex::Lower.encode(&bytes));
+        assert_eq!("001FA0FF", Hex::Upper.encode(&bytes));
+        assert_eq!(Ok(bytes.to_vec()), Hex::decode("001fA0Ff"));
+        assert_eq!(Ok(Vec::new()), Hex::decode(""));
+        for invalid in ["0", "0g", " 00", "0x00"] {
+            assert!(
+                matches!(
+                    Hex::decode(invalid),
+                    Err(CipherError::InvalidEncoding { .. })
+                ),
+                "{invalid}"
+            );
+        }
+    }
+
+    // RFC 4648 section 10.
+    #[test]
+    fn base64_test_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (plain, encoded) in vectors {
+            assert_eq!(encoded, Base64::Standard.encode(plain.as_bytes()));
+            assert_eq!(
+                Ok(plain.as_bytes().to_vec()),
+                Base64::Standard.decode(encoded)
+            );
+
+            let unpadded = encoded.trim_end_matches('=');
+            assert_eq!(unpadded, Base64::StandardUnpadded.encode(plain.as_bytes()));
+            assert_eq!(
+                Ok(plain.as_bytes().to_vec()),
+                Base64::StandardUnpadded.decode(unpadded)
+            );
+        }
+    }
+
+    #[test]
+    fn base64_alphabets() {
+        let bytes = [0xfb, 0xff, 0xbf];
+        assert_eq!("+/+/", Base64::Standard.encode(&bytes));
+        assert_eq!("-_-_", Base64::UrlSafe.encode(&bytes));
+        assert_eq!("-_-_", Base64::UrlSafeUnpadded.encode(&bytes));
+        assert_eq!(Ok(bytes.to_vec()), Base64::UrlSafe.decode("-_-_"));
+        assert!(Base64::UrlSafe.decode("+/+/").is_err());
+        assert!(Base64::Standard.decode("-_-_").is_err());
+    }
+
+    #[test]
+    fn base64_decoding_is_strict() {
+        let invalid = [
+            (Base64::Standard, "Zg="),
+            (Base64::Standard, "Zg"),
+            (Base64::Standard, "Z==="),
+            (Base64::Standard, "Zh=="),
+            (Base64::Standard, "Zm9v\n"),
+            (Base64::Standard, "Zg==Zg=="),
+            (Base64::Standard, "Z=g="),
+            (Base64::Standard, "Zg=a"),
+            (Base64::StandardUnpadded, "Zg=="),
+            (Base64::StandardUnpadded, "Z"),
+            (Base64::UrlSafeUnpadded, "Zm8="),
+        ];
+        for (variant, text) in invalid {
+            assert!(
+                matches!(
+                    variant.decode(text),
+                    Err(CipherError::InvalidEncoding { .. })
+                ),
+                "{variant:?} {text:?}"
+            );
+        }
+    }
+}
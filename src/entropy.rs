@@ -0,0 +1,41 @@
+// Random bytes for initialization vectors.
+use crate::CipherError;
+
+/// Source of random bytes, used to generate initialization vectors.
+/// [`OsEntropy`] reads the operating system generator, tests may plug in a deterministic one.
+pub trait EntropySource {
+    /// Fills whole `bytes` or fails with [`CipherError::EntropyUnavailable`].
+    fn fill(&mut self, bytes: &mut [u8]) -> Result<(), CipherError>;
+}
+
+/// Operating system generator, reads `/dev/urandom`. Fails with
+/// [`CipherError::EntropyUnavailable`] where it doesn't exist, e.g. on Windows. Requires `std`.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OsEntropy;
+
+#[cfg(feature = "std")]
+impl EntropySource for OsEntropy {
+    fn fill(&mut self, bytes: &mut [u8]) -> Result<(), CipherError> {
+        use std::{fs::File, io::Read};
+
+        File::open("/dev/urandom")
+            .and_then(|mut urandom| urandom.read_exact(bytes))
+            .map_err(|_| CipherError::EntropyUnavailable)
+    }
+}
+
+#[cfg(all(test, unix, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn os_entropy_fills_bytes() {
+        let mut first = [0u8; 32];
+        let mut second = [0u8; 32];
+        OsEntropy.fill(&mut first).unwrap();
+        OsEntropy.fill(&mut second).unwrap();
+        assert_ne!(first, second);
+        assert_ne!([0u8; 32], first);
+    }
+}
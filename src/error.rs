@@ -0,0 +1,132 @@
+use core::fmt;
+
+/// Error returned by [`Cipher`](crate::Cipher) and [`Padder`](crate::Padder)s.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CipherError {
+    /// Key length in bits is not one of 128, 192 or 256.
+    InvalidKeyLength { bits: usize },
+    /// Key length is valid, but differs from the one required, e.g. by a [`Transformation`](crate::Transformation).
+    KeySizeMismatch { expected: usize, actual: usize },
+    /// Initialization vector length in bytes is not equal to the block size.
+    InvalidIvLength { len: usize },
+    /// Input can't be padded or the padding of the decrypted input is malformed.
+    InvalidPadding,
+    /// Ciphertext length is not a multiple of the block size.
+    TruncatedInput,
+    /// Input passed for processing in place is not a multiple of the block size.
+    UnalignedInput { len: usize },
+    /// Output buffer is smaller than the number of bytes to be written.
+    OutputTooSmall { required: usize, len: usize },
+    /// Authentication of the message failed.
+    AuthenticationFailed,
+    /// Saved cipher state is malformed or of unknown version, or the cipher uses a custom
+    /// mode or padding, which can't be saved or reset.
+    InvalidState,
+    /// Transformation string is malformed or names an unsupported algorithm, mode or padding.
+    InvalidTransformation { reason: &'static str },
+    /// Random bytes couldn't be read from the entropy source.
+    EntropyUnavailable,
+    /// Hex, base64 or armored text is malformed.
+    InvalidEncoding { reason: &'static str },
+    /// Initialization vector was already used for encryption with the same key.
+    IvReused,
+    /// Initialization vector couldn't be recorded by the store of the guard.
+    IvStoreFailed,
+    /// Key reached one of its usage limits and has to be replaced.
+    KeyExhausted,
+    /// Keyring has no key with the ID, e.g. it was destroyed.
+    UnknownKey { id: u32 },
+    /// Keyring already has a key with the ID.
+    DuplicateKey { id: u32 },
+    /// Retired key can't become the primary key of the keyring.
+    KeyRetired { id: u32 },
+    /// Keyring has no primary key to encrypt with.
+    NoPrimaryKey,
+}
+
+impl fmt::Display for CipherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CipherError::InvalidKeyLength { bits } => write!(
+                f,
+                "Invalid key length. Expected one of 128, 192, 256, got {bits}."
+            ),
+            CipherError::KeySizeMismatch { expected, actual } => write!(
+                f,
+                "Key size mismatch. Expected {expected} bits, got {actual}."
+            ),
+            CipherError::InvalidIvLength { len } => write!(
+                f,
+                "Invalid initialization vector length. Expected 16 bytes, got {len}."
+            ),
+            CipherError::InvalidPadding => write!(f, "Invalid padding."),
+            CipherError::TruncatedInput => {
+                write!(f, "Ciphertext length is not a multiple of block size.")
+            }
+            CipherError::UnalignedInput { len } => {
+                write!(f, "Input length ({len}) is not a multiple of block size.")
+            }
+            CipherError::OutputTooSmall { required, len } => write!(
+                f,
+                "Output buffer is too small. Expected at least {required} bytes, got: {len}."
+            ),
+            CipherError::AuthenticationFailed => write!(f, "Authentication failed."),
+            CipherError::InvalidState => write!(f, "Invalid or unsupported cipher state."),
+            CipherError::InvalidTransformation { reason } => {
+                write!(f, "Invalid transformation: {reason}.")
+            }
+            CipherError::EntropyUnavailable => write!(f, "Entropy source is unavailable."),
+            CipherError::InvalidEncoding { reason } => write!(f, "Invalid encoding: {reason}."),
+            CipherError::IvReused => {
+                write!(f, "Initialization vector was already used with this key.")
+            }
+            CipherError::IvStoreFailed => write!(f, "Initialization vector couldn't be recorded."),
+            CipherError::KeyExhausted => write!(f, "Key usage limit exceeded."),
+            CipherError::UnknownKey { id } => write!(f, "Unknown key {id}."),
+            CipherError::DuplicateKey { id } => write!(f, "Key {id} already exists."),
+            CipherError::KeyRetired { id } => write!(f, "Key {id} is retired."),
+            CipherError::NoPrimaryKey => write!(f, "No primary key."),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CipherError {}
+
+// Used by the stream adapters, malformed input is invalid data for the reader or writer.
+#[cfg(feature = "std")]
+impl From<CipherError> for std::io::Error {
+    fn from(err: CipherError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_includes_details() {
+        assert_eq!(
+            "Invalid key length. Expected one of 128, 192, 256, got 64.",
+            CipherError::InvalidKeyLength { bits: 64 }.to_string()
+        );
+        assert_eq!(
+            "Key size mismatch. Expected 128 bits, got 256.",
+            CipherError::KeySizeMismatch {
+                expected: 128,
+                actual: 256
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "Output buffer is too small. Expected at least 32 bytes, got: 16.",
+            CipherError::OutputTooSmall {
+                required: 32,
+                len: 16
+            }
+            .to_string()
+        );
+    }
+}
@@ -0,0 +1,199 @@
+// Detection of initialization vectors reused with the same key.
+use std::{
+    collections::HashSet,
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use crate::{Block, BlockCipher, CipherError, Mode, BLOCK_SIZE};
+
+/// Blocks encrypted with the key to identify it, see [`fingerprint`].
+const FINGERPRINT_INPUTS: [&Block; 2] = [b"iv-guard key id\0", b"iv-guard key id\x01"];
+
+/// Key fingerprint followed by the IV.
+const RECORD_SIZE: usize = 2 * BLOCK_SIZE;
+
+/// Storage of (key fingerprint, IV) pairs recorded by the [`IvGuard`].
+pub trait IvStore: Send {
+    /// Records the pair, returns `false` if it was recorded before.
+    fn insert(&mut self, fingerprint: &Block, iv: &Block) -> Result<bool, CipherError>;
+}
+
+fn record(fingerprint: &Block, iv: &Block) -> [u8; RECORD_SIZE] {
+    let mut record = [0u8; RECORD_SIZE];
+    record[..BLOCK_SIZE].copy_from_slice(fingerprint);
+    record[BLOCK_SIZE..].copy_from_slice(iv);
+    record
+}
+
+/// Pairs kept in a hash set, forgotten when dropped.
+#[derive(Default)]
+pub struct MemoryIvStore {
+    records: HashSet<[u8; RECORD_SIZE]>,
+}
+
+impl MemoryIvStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+impl IvStore for MemoryIvStore {
+    fn insert(&mut self, fingerprint: &Block, iv: &Block) -> Result<bool, CipherError> {
+        Ok(self.records.insert(record(fingerprint, iv)))
+    }
+}
+
+/// Pairs appended to a file and loaded into a [`MemoryIvStore`] when opened, so they
+/// survive restarts. Every record is synced to disk before the cipher is created.
+pub struct FileIvStore {
+    file: File,
+    memory: MemoryIvStore,
+}
+
+impl FileIvStore {
+    /// Opens or creates the file. A partial record at the end, left by a crash during
+    /// the write, is dropped, the cipher using its IV was never created.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        let whole_len = bytes.len() - bytes.len() % RECORD_SIZE;
+        if whole_len != bytes.len() {
+            // Append only handle isn't allowed to truncate everywhere.
+            OpenOptions::new()
+                .write(true)
+                .open(path)?
+                .set_len(whole_len as u64)?;
+        }
+
+        let mut memory = MemoryIvStore::new();
+        memory.records.extend(
+            bytes[..whole_len]
+                .chunks_exact(RECORD_SIZE)
+                .map(|record| <[u8; RECORD_SIZE]>::try_from(record).unwrap()),
+        );
+        Ok(Self { file, memory })
+    }
+
+    pub fn len(&self) -> usize {
+        self.memory.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.memory.is_empty()
+    }
+}
+
+impl IvStore for FileIvStore {
+    fn insert(&mut self, fingerprint: &Block, iv: &Block) -> Result<bool, CipherError> {
+        let record = record(fingerprint, iv);
+        if self.memory.records.contains(&record) {
+            return Ok(false);
+        }
+        self.file
+            .write_all(&record)
+            .and_then(|_| self.file.sync_data())
+            .map_err(|_| CipherError::IvStoreFailed)?;
+        self.memory.records.insert(record);
+        Ok(true)
+    }
+}
+
+/// Opt-in guard against reusing an IV with the same key for encryption, used by
+/// [`Cipher::init_guarded`](crate::Cipher::init_guarded) and
+/// [`Cipher::reset_guarded`](crate::Cipher::reset_guarded). Keys are identified by
+/// a fingerprint derived from encryptions of constant blocks, so the store doesn't hold
+/// the keys. Readers of the store learn which records belong to the same key.
+pub struct IvGuard {
+    store: Box<dyn IvStore>,
+}
+
+impl IvGuard {
+    pub fn new(store: impl IvStore + 'static) -> Self {
+        Self {
+            store: Box::new(store),
+        }
+    }
+
+    /// Guard with a [`MemoryIvStore`].
+    pub fn in_memory() -> Self {
+        Self::new(MemoryIvStore::new())
+    }
+
+    /// Records the IV for the key, fails with [`CipherError::IvReused`] if it was used before.
+    pub fn record(&mut self, key: &dyn BlockCipher, iv: &Block) -> Result<(), CipherError> {
+        if !self.store.insert(&fingerprint(key), iv)? {
+            return Err(CipherError::IvReused);
+        }
+        Ok(())
+    }
+
+    /// Records the IV of CBC, other modes have no IV known to the guard.
+    pub(crate) fn record_mode(
+        &mut self,
+        key: &dyn BlockCipher,
+        mode: &Mode,
+    ) -> Result<(), CipherError> {
+        match mode {
+            Mode::Cbc(iv) => self.record(key, iv),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// XOR of two encrypted constant blocks. Unlike a single encrypted block, it is no
+/// ciphertext the key could produce in ECB or CBC, and the stored value doesn't give away
+/// a known plaintext and ciphertext pair under the key.
+fn fingerprint(key: &dyn BlockCipher) -> Block {
+    let [first, second] = FINGERPRINT_INPUTS.map(|input| key.encrypt_block(input));
+    core::array::from_fn(|i| first[i] ^ second[i])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Aes128;
+
+    #[test]
+    fn fingerprint_identifies_key() {
+        let key = [0x42; 16];
+        let first = fingerprint(&Aes128::new(&key));
+        assert_eq!(first, fingerprint(&Aes128::new(&key)));
+        assert_ne!(first, fingerprint(&Aes128::new(&[0x43; 16])));
+        assert_ne!(key, first);
+        let aes = Aes128::new(&key);
+        for input in FINGERPRINT_INPUTS {
+            assert_ne!(aes.encrypt_block(input), first);
+        }
+    }
+
+    #[test]
+    fn memory_store_holds_many_records() {
+        let mut store = MemoryIvStore::new();
+        let fingerprint = [7; 16];
+        for i in 0..10_000u128 {
+            assert_eq!(Ok(true), store.insert(&fingerprint, &i.to_be_bytes()));
+        }
+        assert_eq!(10_000, store.len());
+        assert_eq!(
+            Ok(false),
+            store.insert(&fingerprint, &5_000u128.to_be_bytes())
+        );
+        assert_eq!(Ok(true), store.insert(&[8; 16], &5_000u128.to_be_bytes()));
+    }
+}
@@ -1,7 +1,143 @@
+use core::fmt;
+
 use crate::{
+    dec,
     lookup::{RCON_LOOKUP, S_BOX},
-    Block, Word, NB,
+    zeroize::zeroize,
+    Block, CipherError, Word, NB,
 };
+#[cfg(feature = "alloc")]
+use crate::{Aes128, Aes192, Aes256, Backend, BlockCipher};
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+
+/// Owned AES key of 128, 192 or 256 bits. Key bytes are wiped from memory on drop.
+#[derive(Clone)]
+pub struct Key {
+    bytes: KeyBytes,
+}
+
+#[derive(Clone)]
+enum KeyBytes {
+    Aes128([u8; 16]),
+    Aes192([u8; 24]),
+    Aes256([u8; 32]),
+}
+
+impl Key {
+    pub fn new(key: &[u8]) -> Result<Self, CipherError> {
+        let bytes = if let Ok(key) = key.try_into() {
+            KeyBytes::Aes128(key)
+        } else if let Ok(key) = key.try_into() {
+            KeyBytes::Aes192(key)
+        } else if let Ok(key) = key.try_into() {
+            KeyBytes::Aes256(key)
+        } else {
+            return Err(CipherError::InvalidKeyLength {
+                bits: key.len() * 8,
+            });
+        };
+        Ok(Self { bytes })
+    }
+
+    pub fn bits(&self) -> usize {
+        self.as_bytes().len() * 8
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match &self.bytes {
+            KeyBytes::Aes128(key) => key,
+            KeyBytes::Aes192(key) => key,
+            KeyBytes::Aes256(key) => key,
+        }
+    }
+
+    /// Expands the key for the selected backend. Returned cipher owns its round keys,
+    /// so it can outlive the key, see [`Cipher::init_owned`](crate::Cipher::init_owned).
+    #[cfg(feature = "alloc")]
+    pub fn block_cipher(&self, backend: Backend) -> Box<dyn BlockCipher> {
+        match &self.bytes {
+            KeyBytes::Aes128(key) => Box::new(Aes128::with_backend(key, backend)),
+            KeyBytes::Aes192(key) => Box::new(Aes192::with_backend(key, backend)),
+            KeyBytes::Aes256(key) => Box::new(Aes256::with_backend(key, backend)),
+        }
+    }
+}
+
+impl Drop for Key {
+    fn drop(&mut self) {
+        match &mut self.bytes {
+            KeyBytes::Aes128(key) => zeroize(key),
+            KeyBytes::Aes192(key) => zeroize(key),
+            KeyBytes::Aes256(key) => zeroize(key),
+        }
+    }
+}
+
+// Key bytes are never printed.
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Key")
+            .field("bits", &self.bits())
+            .finish_non_exhaustive()
+    }
+}
+
+pub(crate) const MAX_NR: usize = 14;
+pub(crate) const MAX_KEY_SCHEDULE_LEN: usize = NB * (MAX_NR + 1);
+
+/// Expanded round keys for encryption and decryption.
+/// Decryption round keys are stored in the order they are applied during decryption,
+/// with InvMixColumns applied to all but the first and the last one, so that decryption
+/// has the same structure as encryption (equivalent inverse cipher, FIPS-197 section 5.3.5).
+pub(crate) struct KeySchedule {
+    nr: u8,
+    round_keys: [Word; MAX_KEY_SCHEDULE_LEN],
+    dec_round_keys: [Word; MAX_KEY_SCHEDULE_LEN],
+}
+
+impl KeySchedule {
+    pub(crate) fn new(key: &[u8]) -> Self {
+        // Key length is one of 16, 24 or 32 bytes, guaranteed by the keyed cipher types.
+        let nr = (key.len() / 4 + 6) as u8;
+        let round_keys = key_expansion(key, nr as usize);
+
+        let mut dec_round_keys = [[0u8; 4]; MAX_KEY_SCHEDULE_LEN];
+        for round in 0..=(nr as usize) {
+            let enc_round = nr as usize - round;
+            dec_round_keys[round * NB..(round + 1) * NB]
+                .copy_from_slice(&round_keys[enc_round * NB..(enc_round + 1) * NB]);
+        }
+        for word in &mut dec_round_keys[NB..(NB * nr as usize)] {
+            *word = dec::mix_word(word);
+        }
+
+        Self {
+            nr,
+            round_keys,
+            dec_round_keys,
+        }
+    }
+
+    pub(crate) fn nr(&self) -> u8 {
+        self.nr
+    }
+
+    pub(crate) fn round_keys(&self) -> &[Word] {
+        &self.round_keys[..NB * (self.nr as usize + 1)]
+    }
+
+    pub(crate) fn dec_round_keys(&self) -> &[Word] {
+        &self.dec_round_keys[..NB * (self.nr as usize + 1)]
+    }
+}
+
+impl Drop for KeySchedule {
+    fn drop(&mut self) {
+        zeroize(&mut self.round_keys);
+        zeroize(&mut self.dec_round_keys);
+    }
+}
 
 #[allow(clippy::identity_op, clippy::erasing_op)] // for readability
 pub(crate) fn add_round_key(state: &mut Block, key_schedule: &[Word], nr: u8) {
@@ -14,13 +150,13 @@ pub(crate) fn add_round_key(state: &mut Block, key_schedule: &[Word], nr: u8) {
 }
 
 // https://en.wikipedia.org/wiki/AES_key_schedule
-pub(crate) fn key_expansion(key: &[u8], nr: usize) -> Vec<Word> {
+fn key_expansion(key: &[u8], nr: usize) -> [Word; MAX_KEY_SCHEDULE_LEN] {
     let nk = key.len() / 4;
-    let mut w = Vec::<[u8; 4]>::with_capacity(NB * (nr + 1));
+    let mut w = [[0u8; 4]; MAX_KEY_SCHEDULE_LEN];
     let mut temp;
 
-    for i in 0..nk {
-        w.push([key[4 * i], key[4 * i + 1], key[4 * i + 2], key[4 * i + 3]]);
+    for (word, key_word) in w.iter_mut().zip(key.chunks_exact(4)) {
+        word.copy_from_slice(key_word);
     }
 
     for i in nk..(NB * (nr + 1)) {
@@ -30,7 +166,7 @@ pub(crate) fn key_expansion(key: &[u8], nr: usize) -> Vec<Word> {
         } else if nk > 6 && i % nk == 4 {
             temp = sub_word(&temp);
         }
-        w.push(xor_words(&w[i - nk], &temp));
+        w[i] = xor_words(&w[i - nk], &temp);
     }
     w
 }
@@ -56,3 +192,67 @@ fn rot_word(word: &[u8; 4]) -> [u8; 4] {
 fn rcon(i: usize) -> [u8; 4] {
     [RCON_LOOKUP[i - 1], 0, 0, 0]
 }
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use super::*;
+
+    fn to_hex(words: &[Word]) -> String {
+        words.concat().iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    fn assert_dec_round_keys(key: &[u8], expected: &[&str]) {
+        let key_schedule = KeySchedule::new(key);
+        assert_eq!(expected.len(), key_schedule.nr() as usize + 1);
+        for (round, expected) in expected.iter().enumerate() {
+            let round_key = &key_schedule.dec_round_keys()[round * NB..(round + 1) * NB];
+            assert_eq!(*expected, to_hex(round_key), "round {round}");
+        }
+    }
+
+    // FIPS-197 Appendix C.1, round[r].ik_sch of the equivalent inverse cipher.
+    #[test]
+    fn dec_round_keys_128() {
+        let key: Vec<u8> = (0..16).collect();
+        assert_dec_round_keys(&key, &[
+            "13111d7fe3944a17f307a78b4d2b30c5", "13aa29be9c8faff6f770f58000f7bf03",
+            "1362a4638f2586486bff5a76f7874a83", "8d82fc749c47222be4dadc3e9c7810f5",
+            "72e3098d11c5de5f789dfe1578a2cccb", "2ec410276326d7d26958204a003f32de",
+            "a8a2f5044de2c7f50a7ef79869671294", "c7c6e391e54032f1479c306d6319e50c",
+            "a0db02992286d160a2dc029c2485d561", "8c56dff0825dd3f9805ad3fc8659d7fd",
+            "000102030405060708090a0b0c0d0e0f",
+        ]);
+    }
+
+    // FIPS-197 Appendix C.2.
+    #[test]
+    fn dec_round_keys_192() {
+        let key: Vec<u8> = (0..24).collect();
+        assert_dec_round_keys(&key, &[
+            "a4970a331a78dc09c418c271e3a41d5d", "d6bebd0dc209ea494db073803e021bb9",
+            "8fb999c973b26839c7f9d89d85c68c72", "f77d6ec1423f54ef5378317f14b75744",
+            "1147659047cf663b9b0ece8dfc0bf1f0", "dcc1a8b667053f7dcc5c194ab5423a2e",
+            "c6deb0ab791e2364a4055fbe568803ab", "dd1b7cdaf28d5c158a49ab1dbbc497cb",
+            "78c4f708318d3cd69655b701bfc093cf", "60dcef10299524ce62dbef152f9620cf",
+            "4b4ecbdb4d4dcfda5752d7c74949cbde", "1a1f181d1e1b1c194742c7d74949cbde",
+            "000102030405060708090a0b0c0d0e0f",
+        ]);
+    }
+
+    // FIPS-197 Appendix C.3.
+    #[test]
+    fn dec_round_keys_256() {
+        let key: Vec<u8> = (0..32).collect();
+        assert_dec_round_keys(&key, &[
+            "24fc79ccbf0979e9371ac23c6d68de36", "34f1d1ffbfceaa2ffce9e25f2558016e",
+            "5e1648eb384c350a7571b746dc80e684", "c8a305808b3f7bd043274870d9b1e331",
+            "b5708e13665a7de14d3d824ca9f151c2", "74da7ba3439c7e50c81833a09a96ab41",
+            "3ca69715d32af3f22b67ffade4ccd38e", "f85fc4f3374605f38b844df0528e98e1",
+            "de69409aef8c64e7f84d0c5fcfab2c23", "aed55816cf19c100bcc24803d90ad511",
+            "15c668bd31e5247d17c168b837e6207c", "7fd7850f61cc991673db890365c89d12",
+            "2a2840c924234cc026244cc5202748c4", "1a1f181d1e1b1c191217101516131411",
+            "000102030405060708090a0b0c0d0e0f",
+        ]);
+    }
+}
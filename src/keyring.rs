@@ -0,0 +1,163 @@
+// Versioned keys, ciphertexts are tagged with the ID of the key used for encryption.
+//
+// Layout: format version | key ID (4 bytes, big endian) | IV | CBC ciphertext
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{entropy::EntropySource, seal, Backend, BlockCipher, CipherError, Key, BLOCK_SIZE};
+
+const FORMAT_VERSION: u8 = 1;
+const HEADER_SIZE: usize = 5;
+
+/// State of a key in the [`Keyring`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyStatus {
+    /// Encrypts new messages, at most one key is primary.
+    Primary,
+    /// Decrypts messages, can be made primary.
+    Active,
+    /// Only decrypts old messages.
+    Retired,
+}
+
+struct Entry {
+    id: u32,
+    status: KeyStatus,
+    cipher: Box<dyn BlockCipher>,
+}
+
+/// Keys identified by an application chosen ID, e.g. a version number incremented on
+/// every rotation. [`Keyring::encrypt`] uses the primary key and writes its ID in front of
+/// the message, [`Keyring::decrypt`] finds the key by that ID. Messages are encrypted
+/// with CBC under a random IV, see [`seal`](crate::seal()).
+///
+/// Only expanded keys are kept, their round keys are wiped when destroyed or dropped.
+///
+/// Messages are not authenticated, neither the header nor the ciphertext. Changed key ID
+/// makes [`Keyring::decrypt`] use another key of the keyring, which is usually detected
+/// only by the padding check, so messages from untrusted storage need a MAC on top.
+#[derive(Default)]
+pub struct Keyring {
+    entries: Vec<Entry>,
+}
+
+impl Keyring {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an active key, fails with [`CipherError::DuplicateKey`] if the ID is taken.
+    pub fn add(&mut self, id: u32, key: Key) -> Result<(), CipherError> {
+        if self.entry(id).is_ok() {
+            return Err(CipherError::DuplicateKey { id });
+        }
+        self.entries.push(Entry {
+            id,
+            status: KeyStatus::Active,
+            cipher: key.block_cipher(Backend::default()),
+        });
+        Ok(())
+    }
+
+    /// Makes the key primary, previous primary key stays active. Retired keys can't be primary.
+    pub fn set_primary(&mut self, id: u32) -> Result<(), CipherError> {
+        if self.entry(id)?.status == KeyStatus::Retired {
+            return Err(CipherError::KeyRetired { id });
+        }
+        for entry in &mut self.entries {
+            if entry.status == KeyStatus::Primary {
+                entry.status = KeyStatus::Active;
+            }
+            if entry.id == id {
+                entry.status = KeyStatus::Primary;
+            }
+        }
+        Ok(())
+    }
+
+    /// Keeps the key only for decryption. Retiring the primary key leaves the keyring
+    /// without one until [`Keyring::set_primary`].
+    pub fn retire(&mut self, id: u32) -> Result<(), CipherError> {
+        let index = self.index(id)?;
+        self.entries[index].status = KeyStatus::Retired;
+        Ok(())
+    }
+
+    /// Removes the key and wipes its round keys, messages encrypted with it can't be
+    /// decrypted anymore.
+    pub fn destroy(&mut self, id: u32) -> Result<(), CipherError> {
+        let index = self.index(id)?;
+        self.entries.remove(index);
+        Ok(())
+    }
+
+    pub fn primary(&self) -> Option<u32> {
+        self.entries
+            .iter()
+            .find(|entry| entry.status == KeyStatus::Primary)
+            .map(|entry| entry.id)
+    }
+
+    pub fn status(&self, id: u32) -> Option<KeyStatus> {
+        self.entry(id).ok().map(|entry| entry.status)
+    }
+
+    /// IDs of all keys in the order they were added.
+    pub fn ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.entries.iter().map(|entry| entry.id)
+    }
+
+    /// Encrypts with the primary key under a fresh IV from the [`OsEntropy`](crate::OsEntropy).
+    /// Requires `std`.
+    #[cfg(feature = "std")]
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, CipherError> {
+        self.encrypt_with(plaintext, &mut crate::OsEntropy)
+    }
+
+    /// Same as [`Keyring::encrypt`], but the IV is read from the given `entropy` source.
+    pub fn encrypt_with(
+        &self,
+        plaintext: &[u8],
+        entropy: &mut dyn EntropySource,
+    ) -> Result<Vec<u8>, CipherError> {
+        let id = self.primary().ok_or(CipherError::NoPrimaryKey)?;
+        let sealed = seal::seal_with(self.entry(id)?.cipher.as_ref(), plaintext, entropy)?;
+
+        let mut message = Vec::with_capacity(HEADER_SIZE + sealed.len());
+        message.push(FORMAT_VERSION);
+        message.extend_from_slice(&id.to_be_bytes());
+        message.extend_from_slice(&sealed);
+        Ok(message)
+    }
+
+    /// Decrypts a message of [`Keyring::encrypt`] with the key it was encrypted with,
+    /// primary, active or retired. The key ID in the header is not integrity protected,
+    /// a tampered one fails with [`CipherError::InvalidPadding`] in most cases, but not all.
+    pub fn decrypt(&self, message: &[u8]) -> Result<Vec<u8>, CipherError> {
+        let id = Self::key_id(message)?;
+        seal::open(self.entry(id)?.cipher.as_ref(), &message[HEADER_SIZE..])
+    }
+
+    /// ID of the key the message was encrypted with, read from its header.
+    pub fn key_id(message: &[u8]) -> Result<u32, CipherError> {
+        if message.len() < HEADER_SIZE + BLOCK_SIZE {
+            return Err(CipherError::TruncatedInput);
+        }
+        if message[0] != FORMAT_VERSION {
+            return Err(CipherError::InvalidState);
+        }
+        Ok(u32::from_be_bytes([
+            message[1], message[2], message[3], message[4],
+        ]))
+    }
+
+    fn index(&self, id: u32) -> Result<usize, CipherError> {
+        self.entries
+            .iter()
+            .position(|entry| entry.id == id)
+            .ok_or(CipherError::UnknownKey { id })
+    }
+
+    fn entry(&self, id: u32) -> Result<&Entry, CipherError> {
+        Ok(&self.entries[self.index(id)?])
+    }
+}
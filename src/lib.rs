@@ -1,20 +1,87 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 mod aes;
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+mod aesni;
+#[cfg(feature = "alloc")]
+mod armor;
+mod bitslice;
+mod buffer;
+#[cfg(feature = "alloc")]
+mod checkpoint;
 mod dec;
 mod enc;
+#[cfg(feature = "alloc")]
+mod encoding;
+mod entropy;
+mod error;
+#[cfg(feature = "std")]
+mod iv_guard;
 mod key;
+#[cfg(feature = "alloc")]
+mod keyring;
 mod lookup;
 mod mode;
 mod padding;
+#[cfg(feature = "alloc")]
+mod seal;
 mod state;
-
-use padding::{Padder, PKCS7};
+#[cfg(feature = "std")]
+mod stream;
+mod transformation;
+mod ttable;
+#[cfg(feature = "std")]
+mod usage;
+mod zeroize;
+
+use buffer::Buffer;
+use core::ops::Deref;
+use padding::{NoPadding, PKCS7};
+use zeroize::zeroize;
+
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, vec, vec::Vec};
+#[cfg(feature = "std")]
+use std::num::NonZeroUsize;
+
+pub use aes::{Aes128, Aes192, Aes256, Backend, BlockCipher};
+#[cfg(feature = "alloc")]
+pub use armor::Armor;
+#[cfg(feature = "alloc")]
+pub use checkpoint::Checkpoint;
+#[cfg(feature = "alloc")]
+pub use encoding::{Base64, Hex};
+pub use entropy::EntropySource;
+#[cfg(feature = "std")]
+pub use entropy::OsEntropy;
+pub use error::CipherError;
+#[cfg(feature = "std")]
+pub use iv_guard::{FileIvStore, IvGuard, IvStore, MemoryIvStore};
+pub use key::Key;
+#[cfg(feature = "alloc")]
+pub use keyring::{KeyStatus, Keyring};
+pub use mode::BlockMode;
+pub use padding::Padder;
+#[cfg(feature = "std")]
+pub use seal::seal;
+#[cfg(feature = "alloc")]
+pub use seal::{open, seal_with};
+#[cfg(feature = "std")]
+pub use stream::{DecryptingReader, EncryptingWriter};
+pub use transformation::Transformation;
+#[cfg(feature = "std")]
+pub use usage::{KeyUsage, MeteredCipher, UsageLimits};
 
 pub(crate) const NB: usize = 4;
-pub(crate) const BLOCK_SIZE: usize = 16;
+pub const BLOCK_SIZE: usize = 16;
 
-pub(crate) type Block = [u8; BLOCK_SIZE];
+pub type Block = [u8; BLOCK_SIZE];
 pub(crate) type Word = [u8; NB];
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Operation {
     Encrypt,
     Decrypt,
@@ -23,19 +90,117 @@ pub enum Operation {
 /// Different modes to use with aes block cipher encryption.
 /// ECB dosen't need additional parameters but it is considered unsecured.
 /// CBC requires initialization vector.
+/// Custom allows to plug in user defined mode of operation.
 pub enum Mode {
     Ecb,
     Cbc(Block),
+    #[cfg(feature = "alloc")]
+    Custom(Box<dyn BlockMode>),
+}
+
+impl Mode {
+    /// CBC mode with initialization vector from a slice, which has to be exactly one block long.
+    pub fn cbc(iv: &[u8]) -> Result<Self, CipherError> {
+        match iv.try_into() {
+            Ok(iv) => Ok(Mode::Cbc(iv)),
+            Err(_) => Err(CipherError::InvalidIvLength { len: iv.len() }),
+        }
+    }
+
+    /// Checks `iv` passed to [`Cipher::reset`] without changing the mode. Custom modes
+    /// check it only in [`BlockMode::reset`].
+    fn check_iv(&self, iv: &[u8]) -> Result<(), CipherError> {
+        match self {
+            Mode::Ecb if !iv.is_empty() => Err(CipherError::InvalidIvLength { len: iv.len() }),
+            Mode::Cbc(_) if iv.len() != BLOCK_SIZE => {
+                Err(CipherError::InvalidIvLength { len: iv.len() })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn encrypt(
+        &mut self,
+        data: &mut [u8],
+        cipher: &dyn BlockCipher,
+        threads: usize,
+    ) -> Result<(), CipherError> {
+        let blocks = mode::as_blocks_mut(data)?;
+        self.with_block_mode(|mode| mode.encrypt_parallel(blocks, cipher, threads))
+    }
+
+    fn decrypt(
+        &mut self,
+        data: &mut [u8],
+        cipher: &dyn BlockCipher,
+        threads: usize,
+    ) -> Result<(), CipherError> {
+        let blocks = mode::as_blocks_mut(data)?;
+        self.with_block_mode(|mode| mode.decrypt_parallel(blocks, cipher, threads))
+    }
+
+    fn with_block_mode<T>(&mut self, f: impl FnOnce(&mut dyn BlockMode) -> T) -> T {
+        match self {
+            Mode::Ecb => f(&mut mode::ecb::Ecb),
+            Mode::Cbc(iv) => f(&mut mode::cbc::Cbc::new(iv)),
+            #[cfg(feature = "alloc")]
+            Mode::Custom(mode) => f(mode.as_mut()),
+        }
+    }
 }
 
 /// Padding options to extend input to the block size.
+/// NoPadding requires input to be a multiple of the block size.
+/// Custom allows to plug in user defined padding scheme.
 pub enum Padding {
     PKCS7,
+    NoPadding,
+    #[cfg(feature = "alloc")]
+    Custom(Box<dyn Padder>),
+}
+
+impl Padding {
+    fn padder(&self) -> &dyn Padder {
+        static PKCS7_PADDER: PKCS7 = PKCS7::new(BLOCK_SIZE);
+        match self {
+            Padding::PKCS7 => &PKCS7_PADDER,
+            Padding::NoPadding => &NoPadding,
+            #[cfg(feature = "alloc")]
+            Padding::Custom(padder) => padder.as_ref(),
+        }
+    }
+
+    fn max_padded_len(&self) -> usize {
+        match self {
+            Padding::NoPadding => 0,
+            // Decryption holds back only the last block for unpadding.
+            _ => BLOCK_SIZE,
+        }
+    }
+
+    fn is_padded(&self) -> bool {
+        !matches!(self, Padding::NoPadding)
+    }
+}
+
+/// Keyed block cipher used by the [`Cipher`], borrowed or owned.
+enum BlockCipherRef<'a> {
+    Borrowed(&'a dyn BlockCipher),
+    #[cfg(feature = "alloc")]
+    Owned(Box<dyn BlockCipher>),
 }
 
-#[allow(unused)]
-#[derive(Debug)]
-pub struct CipherError(String);
+impl<'a> Deref for BlockCipherRef<'a> {
+    type Target = dyn BlockCipher + 'a;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            BlockCipherRef::Borrowed(cipher) => *cipher,
+            #[cfg(feature = "alloc")]
+            BlockCipherRef::Owned(cipher) => cipher.as_ref(),
+        }
+    }
+}
 
 pub struct Cipher<'a> {
     operation: Operation,
@@ -46,12 +211,43 @@ pub struct Cipher<'a> {
 impl<'a> Cipher<'a> {
     pub fn init(
         operation: Operation,
-        key: &'a [u8],
+        key: &'a dyn BlockCipher,
+        mode: Mode,
+        padding: Padding,
+    ) -> Result<Self, CipherError> {
+        Self::init_with(operation, BlockCipherRef::Borrowed(key), mode, padding)
+    }
+
+    /// Same as [`Cipher::init`], but encryption fails with [`CipherError::IvReused`] if
+    /// the CBC IV was already used with the key, according to the `guard`. Requires `std`.
+    #[cfg(feature = "std")]
+    pub fn init_guarded(
+        operation: Operation,
+        key: &'a dyn BlockCipher,
+        mode: Mode,
+        padding: Padding,
+        guard: &mut IvGuard,
+    ) -> Result<Self, CipherError> {
+        // Recorded once the cipher is created, so an exhausted key doesn't burn the IV.
+        let mut cipher = Self::init(operation, key, mode, padding)?;
+        if operation == Operation::Encrypt {
+            let (_, mode, _, _) = cipher.context().parts();
+            guard.record_mode(key, mode)?;
+        }
+        Ok(cipher)
+    }
+
+    fn init_with(
+        operation: Operation,
+        key: BlockCipherRef<'a>,
         mode: Mode,
         padding: Padding,
     ) -> Result<Self, CipherError> {
         let (encryptor, decryptor) = match operation {
-            Operation::Encrypt => (Some(Encryptor::init(key, mode, padding)?), None),
+            Operation::Encrypt => {
+                key.consume(&mode, 1, 0)?;
+                (Some(Encryptor::init(key, mode, padding)?), None)
+            }
             Operation::Decrypt => (None, Some(Decryptor::init(key, mode, padding)?)),
         };
         Ok(Self {
@@ -61,184 +257,419 @@ impl<'a> Cipher<'a> {
         })
     }
 
-    pub fn update(&mut self, data: &[u8]) -> Vec<u8> {
-        match self.operation {
-            Operation::Encrypt => self.encryptor.as_mut().unwrap().update(data),
-            Operation::Decrypt => self.decryptor.as_mut().unwrap().update(data),
+    #[cfg(feature = "alloc")]
+    pub fn update(&mut self, data: &[u8]) -> Result<Vec<u8>, CipherError> {
+        let mut output = vec![0u8; self.update_len(data.len())];
+        self.context().update_into(data, &mut output)?;
+        Ok(output)
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn finalize(mut self) -> Result<Vec<u8>, CipherError> {
+        let mut output = vec![0u8; self.finalize_len()];
+        let len = self.finalize_into(&mut output)?;
+        output.truncate(len);
+        Ok(output)
+    }
+
+    /// Exact number of bytes produced by the update with `input_len` bytes of input.
+    /// Saturates for lengths which don't fit `usize` together with the buffered bytes.
+    pub fn update_len(&mut self, input_len: usize) -> usize {
+        self.context().update_len(input_len)
+    }
+
+    /// Maximum number of bytes produced by the finalization. Decryption produces
+    /// less once the padding is removed.
+    pub fn finalize_len(&mut self) -> usize {
+        self.context().finalize_len()
+    }
+
+    /// Same as [`Cipher::update`], but writes to the `output` instead of allocating.
+    /// Returns number of bytes written, which is equal to [`Cipher::update_len`].
+    pub fn update_into(&mut self, input: &[u8], output: &mut [u8]) -> Result<usize, CipherError> {
+        let output_len = self.update_len(input.len());
+        check_output_len(output, output_len)?;
+        self.context().update_into(input, output)
+    }
+
+    /// Processes `data` in place. Its length has to be a multiple of the block size.
+    /// Returns number of bytes written to the beginning of `data`. Encryption always overwrites
+    /// whole `data`, decryption holds back the last block until more data or finalization.
+    pub fn update_in_place(&mut self, data: &mut [u8]) -> Result<usize, CipherError> {
+        if data.len() % BLOCK_SIZE != 0 {
+            return Err(CipherError::UnalignedInput { len: data.len() });
+        }
+        self.context().update_in_place(data)
+    }
+
+    /// Same as [`Cipher::finalize`], but writes to the `output` instead of allocating.
+    /// Returns number of bytes written.
+    pub fn finalize_into(&mut self, output: &mut [u8]) -> Result<usize, CipherError> {
+        self.context().finalize_into(output)
+    }
+
+    /// Sets the maximum number of threads used to process large updates, 1 by default.
+    /// ECB and CBC decryption are split between threads, CBC encryption is always serial.
+    /// Output is the same regardless of the number of threads. Requires `std`.
+    #[cfg(feature = "std")]
+    pub fn set_threads(&mut self, threads: NonZeroUsize) {
+        self.context().set_threads(threads.get());
+    }
+
+    /// Saves the state of the stream, so it can be continued later with [`Cipher::resume`],
+    /// e.g. after the process restarts. Instead of the key, `key_id` (at most 255 bytes)
+    /// is saved to identify it. Custom modes and paddings can't be saved.
+    #[cfg(feature = "alloc")]
+    pub fn checkpoint(&mut self, key_id: &[u8]) -> Result<Checkpoint, CipherError> {
+        let operation = self.operation;
+        let (_, mode, padding, buffer) = self.context().parts();
+        let iv = match mode {
+            Mode::Ecb => None,
+            Mode::Cbc(iv) => Some(*iv),
+            Mode::Custom(_) => return Err(CipherError::InvalidState),
+        };
+        let padded = match padding {
+            Padding::PKCS7 => true,
+            Padding::NoPadding => false,
+            Padding::Custom(_) => return Err(CipherError::InvalidState),
+        };
+        Checkpoint::new(operation, iv, padded, buffer.as_slice(), key_id)
+    }
+
+    /// Continues the stream saved with [`Cipher::checkpoint`]. `key` has to be the one
+    /// identified by [`Checkpoint::key_id`]. Output is the same as if the stream wasn't interrupted.
+    #[cfg(feature = "alloc")]
+    pub fn resume(checkpoint: &Checkpoint, key: &'a dyn BlockCipher) -> Result<Self, CipherError> {
+        if checkpoint.buffer_len > BLOCK_SIZE {
+            return Err(CipherError::InvalidState);
         }
+        let mode = match checkpoint.iv {
+            Some(iv) => Mode::Cbc(iv),
+            None => Mode::Ecb,
+        };
+        let padding = if checkpoint.padded {
+            Padding::PKCS7
+        } else {
+            Padding::NoPadding
+        };
+        let mut cipher = Self::init(checkpoint.operation, key, mode, padding)?;
+        let (_, _, _, buffer) = cipher.context().parts();
+        buffer.fill_from(&checkpoint.buffer[..checkpoint.buffer_len])?;
+        Ok(cipher)
     }
 
-    pub fn finalize(self) -> Result<Vec<u8>, CipherError> {
+    /// Starts a new message with the same key, mode and padding, e.g. after
+    /// [`Cipher::finalize_into`]. Bytes buffered from the previous message are wiped.
+    /// `iv` replaces the chaining IV of CBC, it has to be empty for ECB. Custom modes
+    /// are reset with [`BlockMode::reset`], the message is counted before they check the IV.
+    pub fn reset(&mut self, iv: &[u8]) -> Result<(), CipherError> {
+        let operation = self.operation;
+        let (cipher, mode, _, buffer) = self.context().parts();
+        mode.check_iv(iv)?;
+        if operation == Operation::Encrypt {
+            cipher.consume(mode, 1, 0)?;
+        }
+        mode.with_block_mode(|mode| mode.reset(iv))?;
+        buffer.clear();
+        Ok(())
+    }
+
+    /// Same as [`Cipher::reset`], but the new IV of CBC encryption is checked by
+    /// the `guard`, see [`Cipher::init_guarded`]. Requires `std`.
+    #[cfg(feature = "std")]
+    pub fn reset_guarded(&mut self, iv: &[u8], guard: &mut IvGuard) -> Result<(), CipherError> {
+        let operation = self.operation;
+        let (cipher, mode, _, _) = self.context().parts();
+        if operation == Operation::Encrypt && matches!(mode, Mode::Cbc(_)) {
+            guard.record_mode(&**cipher, &Mode::cbc(iv)?)?;
+        }
+        self.reset(iv)
+    }
+
+    /// Replaces the key for the next message. Bytes buffered from the previous message
+    /// are wiped, mode keeps its state, so [`Cipher::reset`] should follow to set a new IV.
+    pub fn rekey(&mut self, key: &'a dyn BlockCipher) {
+        self.rekey_with(BlockCipherRef::Borrowed(key));
+    }
+
+    /// Describes the mode and padding as a [`Transformation`], e.g. `AES/CBC/PKCS5Padding`.
+    /// Key length is not known to the cipher, it can be added with
+    /// [`Transformation::with_key_bits`]. Custom modes and paddings have no standard name.
+    pub fn transformation(&mut self) -> Result<Transformation, CipherError> {
+        let (_, mode, padding, _) = self.context().parts();
+        Transformation::from_parts(mode, padding)
+    }
+
+    fn rekey_with(&mut self, key: BlockCipherRef<'a>) {
+        let (cipher, _, _, buffer) = self.context().parts();
+        *cipher = key;
+        buffer.clear();
+    }
+
+    fn context(&mut self) -> &mut dyn InitUpdateFinalize<'a> {
         match self.operation {
-            Operation::Encrypt => self.encryptor.unwrap().finalize(),
-            Operation::Decrypt => self.decryptor.unwrap().finalize(),
+            Operation::Encrypt => self.encryptor.as_mut().unwrap(),
+            Operation::Decrypt => self.decryptor.as_mut().unwrap(),
         }
     }
 }
 
+fn check_output_len(output: &[u8], required: usize) -> Result<(), CipherError> {
+    if output.len() < required {
+        return Err(CipherError::OutputTooSmall {
+            required,
+            len: output.len(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(feature = "alloc")]
+impl Cipher<'static> {
+    /// Same as [`Cipher::init`], but takes ownership of the keyed block cipher,
+    /// so the cipher doesn't borrow anything and can be stored in long-lived structs.
+    pub fn init_owned(
+        operation: Operation,
+        key: Box<dyn BlockCipher>,
+        mode: Mode,
+        padding: Padding,
+    ) -> Result<Self, CipherError> {
+        Self::init_with(operation, BlockCipherRef::Owned(key), mode, padding)
+    }
+
+    /// Same as [`Cipher::init_owned`] with the key expanded for the default [`Backend`].
+    pub fn with_key(
+        operation: Operation,
+        key: &Key,
+        mode: Mode,
+        padding: Padding,
+    ) -> Result<Self, CipherError> {
+        Self::init_owned(
+            operation,
+            key.block_cipher(Backend::default()),
+            mode,
+            padding,
+        )
+    }
+
+    /// Same as [`Cipher::rekey`], but takes ownership of the keyed block cipher.
+    /// Previous owned cipher is dropped, which wipes its round keys.
+    pub fn rekey_owned(&mut self, key: Box<dyn BlockCipher>) {
+        self.rekey_with(BlockCipherRef::Owned(key));
+    }
+}
+
 trait InitUpdateFinalize<'a> {
-    fn init(key: &'a [u8], mode: Mode, padding: Padding) -> Result<Self, CipherError>
+    fn init(key: BlockCipherRef<'a>, mode: Mode, padding: Padding) -> Result<Self, CipherError>
     where
         Self: Sized;
-    fn update(&mut self, data: &[u8]) -> Vec<u8>;
-    fn finalize(&mut self) -> Result<Vec<u8>, CipherError>;
+    fn update_len(&self, input_len: usize) -> usize;
+    fn finalize_len(&self) -> usize;
+    #[cfg(feature = "std")]
+    fn set_threads(&mut self, threads: usize);
+    /// `output` has to fit at least `update_len` bytes.
+    fn update_into(&mut self, input: &[u8], output: &mut [u8]) -> Result<usize, CipherError>;
+    /// `data` length has to be a multiple of the block size.
+    fn update_in_place(&mut self, data: &mut [u8]) -> Result<usize, CipherError>;
+    fn finalize_into(&mut self, output: &mut [u8]) -> Result<usize, CipherError>;
+    /// Keyed cipher, mode, padding and buffered bytes.
+    fn parts(&mut self) -> (&mut BlockCipherRef<'a>, &mut Mode, &Padding, &mut Buffer);
 }
 
 struct Encryptor<'a> {
-    key: &'a [u8],
+    cipher: BlockCipherRef<'a>,
     mode: Mode,
     padding: Padding,
-    buffer: Vec<u8>,
+    buffer: Buffer,
+    threads: usize,
 }
 
 impl<'a> InitUpdateFinalize<'a> for Encryptor<'a> {
-    fn init(key: &'a [u8], mode: Mode, padding: Padding) -> Result<Self, CipherError> {
-        if ![16, 24, 32].contains(&key.len()) {
-            return Err(CipherError(format!(
-                "Ivalid key length for AES algorithm. Expected one of [128, 192, 256], got: {}",
-                key.len() * 8
-            )));
-        };
+    fn init(key: BlockCipherRef<'a>, mode: Mode, padding: Padding) -> Result<Self, CipherError> {
         Ok(Self {
-            key,
+            cipher: key,
             mode,
             padding,
-            buffer: Vec::with_capacity(BLOCK_SIZE),
+            buffer: Buffer::new(),
+            threads: 1,
         })
     }
 
-    fn update(&mut self, data: &[u8]) -> Vec<u8> {
-        let output_size = self.buffer.len() + (data.len() - (data.len() % BLOCK_SIZE));
-        let mut output = Vec::with_capacity(output_size);
+    fn update_len(&self, input_len: usize) -> usize {
+        // Every complete block is encrypted right away. Saturates for lengths no slice can have.
+        let len = self.buffer.len().saturating_add(input_len);
+        len - (len % BLOCK_SIZE)
+    }
 
-        // Extend buffer to match the block size.
-        let buffer_ext_len = BLOCK_SIZE - self.buffer.len();
-        let (buffer_ext, remaining) = data.split_at(buffer_ext_len);
-        self.buffer.extend(buffer_ext);
+    fn finalize_len(&self) -> usize {
+        self.padding.max_padded_len()
+    }
 
-        // Encrypt data from the buffer, append it to output and clear the buffer.
-        let ciphertext = match self.mode {
-            Mode::Ecb => mode::ecb::encrypt(&self.buffer, self.key),
-            Mode::Cbc(iv) => {
-                let (output, new_iv) = mode::cbc::encrypt(&self.buffer, self.key, &iv);
-                self.mode = Mode::Cbc(new_iv);
-                output
-            }
-        };
-        output.extend(ciphertext);
-        self.buffer.clear();
+    #[cfg(feature = "std")]
+    fn set_threads(&mut self, threads: usize) {
+        self.threads = threads;
+    }
 
-        // Carry over leftovers for next update or finalize
-        let (fixed_remaining, leftovers) =
-            remaining.split_at(remaining.len() - (remaining.len() % BLOCK_SIZE));
-        self.buffer.extend(leftovers);
-
-        // Encrypt rest of the data and append to the output.
-        let ciphertext = match self.mode {
-            Mode::Ecb => mode::ecb::encrypt(fixed_remaining, self.key),
-            Mode::Cbc(iv) => {
-                let (output, new_iv) = mode::cbc::encrypt(fixed_remaining, self.key, &iv);
-                self.mode = Mode::Cbc(new_iv);
-                output
-            }
-        };
-        output.extend(ciphertext);
+    fn update_into(&mut self, input: &[u8], output: &mut [u8]) -> Result<usize, CipherError> {
+        let output_len = self.update_len(input.len());
+        self.consume(output_len)?;
+        self.buffer.drain_into(input, output, output_len);
+        self.mode
+            .encrypt(&mut output[..output_len], &*self.cipher, self.threads)?;
+        Ok(output_len)
+    }
 
-        output
+    fn update_in_place(&mut self, data: &mut [u8]) -> Result<usize, CipherError> {
+        let output_len = self.update_len(data.len());
+        self.consume(output_len)?;
+        self.buffer.drain_in_place(data, output_len);
+        self.mode
+            .encrypt(&mut data[..output_len], &*self.cipher, self.threads)?;
+        Ok(output_len)
     }
 
-    fn finalize(&mut self) -> Result<Vec<u8>, CipherError> {
-        let padder = match self.padding {
-            Padding::PKCS7 => PKCS7::new(BLOCK_SIZE),
-        };
+    fn parts(&mut self) -> (&mut BlockCipherRef<'a>, &mut Mode, &Padding, &mut Buffer) {
+        (
+            &mut self.cipher,
+            &mut self.mode,
+            &self.padding,
+            &mut self.buffer,
+        )
+    }
 
-        let plaintext = match padder.pad(&self.buffer) {
-            Ok(pt) => pt,
-            Err(err) => return Err(CipherError(err.0)),
-        };
+    fn finalize_into(&mut self, output: &mut [u8]) -> Result<usize, CipherError> {
+        let mut plaintext: Block = [0u8; BLOCK_SIZE];
+        let result = self.pad_and_encrypt(&mut plaintext, output);
+        zeroize(&mut plaintext);
+        result
+    }
+}
 
-        let ciphertext = match self.mode {
-            Mode::Ecb => mode::ecb::encrypt(&plaintext, self.key),
-            Mode::Cbc(iv) => {
-                let (output, _new_iv) = mode::cbc::encrypt(&plaintext, self.key, &iv);
-                output
-            }
-        };
+impl<'a> Encryptor<'a> {
+    /// Counts `len` bytes against the usage limits of the key before the buffer or
+    /// the output are touched, so exceeding them leaves the cipher as it was.
+    fn consume(&self, len: usize) -> Result<(), CipherError> {
+        self.cipher
+            .consume(&self.mode, 0, (len / BLOCK_SIZE) as u64)
+    }
 
-        Ok(ciphertext)
+    fn pad_and_encrypt(
+        &mut self,
+        plaintext: &mut Block,
+        output: &mut [u8],
+    ) -> Result<usize, CipherError> {
+        let len = self
+            .padding
+            .padder()
+            .pad(self.buffer.as_slice(), plaintext)?;
+        // Only unpadded input may end without a final block, padding can't exceed it.
+        if (len == 0 && self.padding.is_padded()) || len % BLOCK_SIZE != 0 || len > plaintext.len()
+        {
+            return Err(CipherError::InvalidPadding);
+        }
+        check_output_len(output, len)?;
+        self.consume(len)?;
+
+        self.mode.encrypt(&mut plaintext[..len], &*self.cipher, 1)?;
+        output[..len].copy_from_slice(&plaintext[..len]);
+        self.buffer.clear();
+
+        Ok(len)
     }
 }
 
 struct Decryptor<'a> {
-    key: &'a [u8],
+    cipher: BlockCipherRef<'a>,
     mode: Mode,
     padding: Padding,
-    buffer: Vec<u8>,
+    buffer: Buffer,
+    threads: usize,
 }
 
 impl<'a> InitUpdateFinalize<'a> for Decryptor<'a> {
-    fn init(key: &'a [u8], mode: Mode, padding: Padding) -> Result<Self, CipherError> {
-        if ![16, 24, 32].contains(&key.len()) {
-            return Err(CipherError(format!(
-                "Ivalid key length for AES algorithm. Expected one of [128, 192, 256], got: {}",
-                key.len() * 8
-            )));
-        };
+    fn init(key: BlockCipherRef<'a>, mode: Mode, padding: Padding) -> Result<Self, CipherError> {
         Ok(Self {
-            key,
+            cipher: key,
             mode,
             padding,
-            buffer: Vec::with_capacity(BLOCK_SIZE),
+            buffer: Buffer::new(),
+            threads: 1,
         })
     }
 
-    fn update(&mut self, data: &[u8]) -> Vec<u8> {
-        // todo: refactor
-        let mut buf = Vec::<u8>::with_capacity(self.buffer.len() + data.len());
-        buf.extend(&self.buffer);
-        buf.extend(data);
-        self.buffer.clear();
-
-        if buf.len() <= BLOCK_SIZE {
-            self.buffer.extend(&buf);
-            return vec![];
+    fn update_len(&self, input_len: usize) -> usize {
+        // Last block is held back for unpadding on finalization,
+        // unless input ends with incomplete block or there is no padding.
+        let len = self.buffer.len().saturating_add(input_len);
+        match len % BLOCK_SIZE {
+            0 if self.padding.is_padded() => len.saturating_sub(BLOCK_SIZE),
+            leftovers => len - leftovers,
         }
+    }
 
-        let (prior, last_block) = buf.split_at(buf.len() - BLOCK_SIZE);
-        let mut output = Vec::with_capacity(prior.len());
+    fn finalize_len(&self) -> usize {
+        self.buffer.len()
+    }
 
-        let ciphertext = match self.mode {
-            Mode::Ecb => mode::ecb::decrypt(prior, self.key),
-            Mode::Cbc(iv) => {
-                let (output, new_iv) = mode::cbc::decrypt(prior, self.key, &iv);
-                self.mode = Mode::Cbc(new_iv);
-                output
-            }
-        };
-        self.buffer.extend(last_block);
-        output.extend(ciphertext);
+    #[cfg(feature = "std")]
+    fn set_threads(&mut self, threads: usize) {
+        self.threads = threads;
+    }
 
-        output
+    fn update_into(&mut self, input: &[u8], output: &mut [u8]) -> Result<usize, CipherError> {
+        let output_len = self.update_len(input.len());
+        self.buffer.drain_into(input, output, output_len);
+        self.mode
+            .decrypt(&mut output[..output_len], &*self.cipher, self.threads)?;
+        Ok(output_len)
     }
 
-    fn finalize(&mut self) -> Result<Vec<u8>, CipherError> {
-        let padder = match self.padding {
-            Padding::PKCS7 => PKCS7::new(BLOCK_SIZE),
-        };
+    fn update_in_place(&mut self, data: &mut [u8]) -> Result<usize, CipherError> {
+        let output_len = self.update_len(data.len());
+        self.buffer.drain_in_place(data, output_len);
+        self.mode
+            .decrypt(&mut data[..output_len], &*self.cipher, self.threads)?;
+        Ok(output_len)
+    }
 
-        let plaintext = match self.mode {
-            Mode::Ecb => mode::ecb::decrypt(&self.buffer, self.key),
-            Mode::Cbc(iv) => {
-                let (output, _new_iv) = mode::cbc::decrypt(&self.buffer, self.key, &iv);
-                output
-            }
-        };
+    fn parts(&mut self) -> (&mut BlockCipherRef<'a>, &mut Mode, &Padding, &mut Buffer) {
+        (
+            &mut self.cipher,
+            &mut self.mode,
+            &self.padding,
+            &mut self.buffer,
+        )
+    }
 
-        let unpadded_plaintext = match padder.unpad(&plaintext) {
-            Ok(pt) => pt,
-            Err(err) => return Err(CipherError(err.0)),
-        };
+    fn finalize_into(&mut self, output: &mut [u8]) -> Result<usize, CipherError> {
+        if !self.padding.is_padded() && self.buffer.len() == 0 {
+            return Ok(0);
+        }
+        if self.buffer.len() != BLOCK_SIZE {
+            return Err(CipherError::TruncatedInput);
+        }
+
+        let mut plaintext: Block = self.buffer.as_slice().try_into().unwrap();
+        let result = self.decrypt_and_unpad(&mut plaintext, output);
+        zeroize(&mut plaintext);
+        result
+    }
+}
+
+impl<'a> Decryptor<'a> {
+    fn decrypt_and_unpad(
+        &mut self,
+        plaintext: &mut Block,
+        output: &mut [u8],
+    ) -> Result<usize, CipherError> {
+        self.mode.decrypt(plaintext, &*self.cipher, 1)?;
+
+        let unpadded_plaintext = self.padding.padder().unpad(plaintext)?;
+        check_output_len(output, unpadded_plaintext.len())?;
+
+        output[..unpadded_plaintext.len()].copy_from_slice(unpadded_plaintext);
+        self.buffer.clear();
 
-        Ok(unpadded_plaintext.to_vec())
+        Ok(unpadded_plaintext.len())
     }
 }
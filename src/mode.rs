@@ -1,2 +1,111 @@
 pub(crate) mod cbc;
 pub(crate) mod ecb;
+
+use crate::{Block, BlockCipher, CipherError, BLOCK_SIZE};
+
+/// Mode of operation chaining the blocks of a [`BlockCipher`], see [`Mode::Custom`](crate::Mode::Custom).
+///
+/// [`Cipher`](crate::Cipher) buffers the input and passes only whole blocks, in order, to `encrypt`
+/// or `decrypt`, so the mode keeps the state chaining consecutive calls (e.g. the last ciphertext
+/// block or a counter) by itself. Padding is added before the last call of `encrypt` and removed
+/// after the last call of `decrypt`.
+pub trait BlockMode {
+    fn encrypt(
+        &mut self,
+        blocks: &mut [Block],
+        cipher: &dyn BlockCipher,
+    ) -> Result<(), CipherError>;
+    fn decrypt(
+        &mut self,
+        blocks: &mut [Block],
+        cipher: &dyn BlockCipher,
+    ) -> Result<(), CipherError>;
+
+    /// Same as [`BlockMode::encrypt`], but may split the blocks between up to `threads` threads,
+    /// see [`Cipher::set_threads`](crate::Cipher::set_threads). Encrypts on the current thread
+    /// by default.
+    fn encrypt_parallel(
+        &mut self,
+        blocks: &mut [Block],
+        cipher: &dyn BlockCipher,
+        _threads: usize,
+    ) -> Result<(), CipherError> {
+        self.encrypt(blocks, cipher)
+    }
+
+    /// Same as [`BlockMode::decrypt`], but may split the blocks between up to `threads` threads.
+    /// Decrypts on the current thread by default.
+    fn decrypt_parallel(
+        &mut self,
+        blocks: &mut [Block],
+        cipher: &dyn BlockCipher,
+        _threads: usize,
+    ) -> Result<(), CipherError> {
+        self.decrypt(blocks, cipher)
+    }
+
+    /// Starts a new message with the initialization vector `iv`, see [`Cipher::reset`](crate::Cipher::reset).
+    /// Modes which can't be reset keep the default, which returns [`CipherError::InvalidState`].
+    fn reset(&mut self, iv: &[u8]) -> Result<(), CipherError> {
+        let _ = iv;
+        Err(CipherError::InvalidState)
+    }
+}
+
+/// Number of blocks passed at once to `BlockCipher::decrypt_blocks` by modes
+/// which need a copy of the ciphertext, enough to fill interleaved lanes of every backend.
+pub(crate) const BATCH_BLOCKS: usize = 8;
+
+/// Spawning a thread for less data is slower than processing it on the current one.
+#[cfg(feature = "std")]
+const MIN_BLOCKS_PER_THREAD: usize = 256;
+
+/// Number of blocks in the chunks `blocks` are split into to be processed by at most
+/// `threads` threads. Equals `blocks` when it's not worth to split the work.
+#[cfg(feature = "std")]
+pub(crate) fn chunk_blocks(blocks: usize, threads: usize) -> usize {
+    let threads = threads.min(blocks / MIN_BLOCKS_PER_THREAD);
+    if threads <= 1 {
+        return blocks;
+    }
+    blocks.div_ceil(threads)
+}
+
+/// Reinterprets `data` as blocks, its length has to be a multiple of the block size.
+pub(crate) fn as_blocks_mut(data: &mut [u8]) -> Result<&mut [Block], CipherError> {
+    if data.len() % BLOCK_SIZE != 0 {
+        return Err(CipherError::UnalignedInput { len: data.len() });
+    }
+    // SAFETY: Block is an array of bytes, so it has the same alignment as u8 and no padding.
+    // Length is a multiple of the block size, so all bytes are covered by the returned slice.
+    Ok(unsafe {
+        core::slice::from_raw_parts_mut(data.as_mut_ptr() as *mut Block, data.len() / BLOCK_SIZE)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn chunk_blocks_splits_evenly() {
+        let min = MIN_BLOCKS_PER_THREAD;
+        assert_eq!(0, chunk_blocks(0, 4));
+        assert_eq!(min, chunk_blocks(min, 4));
+        assert_eq!(4 * min, chunk_blocks(4 * min, 1));
+        assert_eq!(min, chunk_blocks(4 * min, 4));
+        assert_eq!(min, chunk_blocks(4 * min, 8));
+        assert_eq!((4 * min + 2).div_ceil(3), chunk_blocks(4 * min + 2, 3));
+    }
+
+    #[test]
+    fn as_blocks_mut_rejects_unaligned_data() {
+        let mut data = [0u8; 33];
+        assert_eq!(2, as_blocks_mut(&mut data[..32]).unwrap().len());
+        assert_eq!(
+            Err(CipherError::UnalignedInput { len: 33 }),
+            as_blocks_mut(&mut data)
+        );
+    }
+}
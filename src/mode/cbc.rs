@@ -1,57 +1,95 @@
-use std::iter::zip;
+use core::iter::zip;
 
-use crate::{aes, Block, BLOCK_SIZE};
+use super::{BlockMode, BATCH_BLOCKS};
+use crate::{Block, BlockCipher, CipherError, BLOCK_SIZE};
 
-pub(crate) fn encrypt(plaintext: &[u8], key: &[u8], iv: &Block) -> (Vec<u8>, Block) {
-    assert_eq!(
-        0,
-        plaintext.len() % BLOCK_SIZE,
-        "Input length is not a multiply of block size."
-    );
-
-    let mut output = Vec::<u8>::with_capacity(plaintext.len());
+/// Cipher block chaining, every plaintext block is XORed with the previous ciphertext block
+/// before encryption. Initialization vector is replaced with the last ciphertext block
+/// after every call, so it chains the next one.
+pub(crate) struct Cbc<'a> {
+    iv: &'a mut Block,
+}
 
-    let first_block: &Block = &plaintext[0..BLOCK_SIZE].try_into().unwrap();
-    let mut c = aes::encrypt_block(&xor_blocks(first_block, iv), key);
-    output.extend(&c);
+impl<'a> Cbc<'a> {
+    pub(crate) fn new(iv: &'a mut Block) -> Self {
+        Self { iv }
+    }
+}
 
-    for i in 1..(plaintext.len() / BLOCK_SIZE) {
-        let block: &Block = &plaintext[(i * BLOCK_SIZE)..((i + 1) * BLOCK_SIZE)]
+impl BlockMode for Cbc<'_> {
+    fn reset(&mut self, iv: &[u8]) -> Result<(), CipherError> {
+        *self.iv = iv
             .try_into()
-            .unwrap();
-        let encrypted_block = aes::encrypt_block(&xor_blocks(block, &c), key);
-        output.extend(encrypted_block);
-        c = encrypted_block;
+            .map_err(|_| CipherError::InvalidIvLength { len: iv.len() })?;
+        Ok(())
     }
-    (output, c)
-}
 
-pub(crate) fn decrypt(ciphertext: &[u8], key: &[u8], iv: &Block) -> (Vec<u8>, Block) {
-    assert_eq!(
-        0,
-        ciphertext.len() % BLOCK_SIZE,
-        "Input length is not a multiply of block size."
-    );
+    fn encrypt(
+        &mut self,
+        blocks: &mut [Block],
+        cipher: &dyn BlockCipher,
+    ) -> Result<(), CipherError> {
+        let mut c = *self.iv;
+        for block in blocks {
+            c = cipher.encrypt_block(&xor_blocks(block, &c));
+            *block = c;
+        }
+        *self.iv = c;
+        Ok(())
+    }
 
-    let mut output = Vec::<u8>::with_capacity(ciphertext.len());
+    fn decrypt(
+        &mut self,
+        blocks: &mut [Block],
+        cipher: &dyn BlockCipher,
+    ) -> Result<(), CipherError> {
+        *self.iv = decrypt_blocks(blocks, cipher, self.iv);
+        Ok(())
+    }
 
-    let first_block: &Block = &ciphertext[0..BLOCK_SIZE].try_into().unwrap();
-    let decrypted_first_block = aes::decrypt_block(first_block, key);
-    output.extend(xor_blocks(&decrypted_first_block, iv));
+    // Encryption chains every block with the previous one, so only decryption runs in parallel.
+    // Each chunk is chained with the last ciphertext block of the previous one,
+    // read before it's decrypted.
+    #[cfg(feature = "std")]
+    fn decrypt_parallel(
+        &mut self,
+        blocks: &mut [Block],
+        cipher: &dyn BlockCipher,
+        threads: usize,
+    ) -> Result<(), CipherError> {
+        let chunk_blocks = super::chunk_blocks(blocks.len(), threads);
+        if chunk_blocks >= blocks.len() {
+            return self.decrypt(blocks, cipher);
+        }
 
-    let mut c: Block = *iv;
-    for i in 1..(ciphertext.len() / BLOCK_SIZE) {
-        let prev_block: &Block = &ciphertext[((i - 1) * BLOCK_SIZE)..(i * BLOCK_SIZE)]
-            .try_into()
-            .unwrap();
-        let block: &Block = &ciphertext[(i * BLOCK_SIZE)..((i + 1) * BLOCK_SIZE)]
-            .try_into()
-            .unwrap();
-        let decrypted_block = aes::decrypt_block(block, key);
-        output.extend(xor_blocks(&decrypted_block, prev_block));
-        c = *block;
+        let mut c = *self.iv;
+        std::thread::scope(|scope| {
+            for chunk in blocks.chunks_mut(chunk_blocks) {
+                let chunk_iv = c;
+                c = chunk[chunk.len() - 1];
+                scope.spawn(move || decrypt_blocks(chunk, cipher, &chunk_iv));
+            }
+        });
+        *self.iv = c;
+        Ok(())
     }
-    (output, c)
+}
+
+fn decrypt_blocks(blocks: &mut [Block], cipher: &dyn BlockCipher, iv: &Block) -> Block {
+    // Blocks are decrypted independently, chaining only needs the ciphertext.
+    let mut c = *iv;
+    for blocks in blocks.chunks_mut(BATCH_BLOCKS) {
+        let mut ciphertext = [[0u8; BLOCK_SIZE]; BATCH_BLOCKS];
+        let ciphertext = &mut ciphertext[..blocks.len()];
+        ciphertext.copy_from_slice(blocks);
+
+        cipher.decrypt_blocks(blocks);
+        for (block, ciphertext_block) in blocks.iter_mut().zip(ciphertext.iter()) {
+            *block = xor_blocks(block, &c);
+            c = *ciphertext_block;
+        }
+    }
+    c
 }
 
 fn xor_blocks(a: &Block, b: &Block) -> Block {
@@ -66,6 +104,7 @@ fn xor_blocks(a: &Block, b: &Block) -> Block {
 #[rustfmt::skip]
 mod tests {
     use super::*;
+    use crate::{mode::as_blocks_mut, Aes128};
 
     #[test]
     fn test() {
@@ -81,17 +120,47 @@ mod tests {
             0x73, 0xbe, 0xd6, 0xb8, 0xe3, 0xc1, 0x74, 0x3b, 0x71, 0x16, 0xe6, 0x9e, 0x22, 0x22, 0x95, 0x16,
             0x3f, 0xf1, 0xca, 0xa1, 0x68, 0x1f, 0xac, 0x09, 0x12, 0x0e, 0xca, 0x30, 0x75, 0x86, 0xe1, 0xa7,
         ];
-        let key: &[u8] = &[
+        let key = &[
             0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c,
         ];
         let iv: &Block = &[
             0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
         ];
 
-        let (encrypted, _new_iv) = encrypt(plaintext, key, iv);
-        let (decrypted, _new_iv) = decrypt(ciphertext, key, iv);
+        let cipher = Aes128::new(key);
+
+        let mut encrypted = plaintext.to_vec();
+        let mut new_iv = *iv;
+        Cbc::new(&mut new_iv).encrypt(as_blocks_mut(&mut encrypted).unwrap(), &cipher).unwrap();
+        assert_eq!(&ciphertext[48..], new_iv);
+        let mut decrypted = ciphertext.to_vec();
+        let mut new_iv = *iv;
+        Cbc::new(&mut new_iv).decrypt(as_blocks_mut(&mut decrypted).unwrap(), &cipher).unwrap();
+        assert_eq!(&ciphertext[48..], new_iv);
 
         assert_eq!(ciphertext, encrypted);
         assert_eq!(plaintext, decrypted);
     }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn parallel_decrypt_matches_serial() {
+        let cipher = Aes128::new(&[0x42; 16]);
+        let iv = [0x24; 16];
+        let mut seed = 0x2545f4914f6cdd1d;
+        let mut plaintext = vec![0u8; 4 * 256 * BLOCK_SIZE + 48];
+        crate::aes::tests::random_bytes(&mut seed, &mut plaintext);
+
+        let mut ciphertext = plaintext.clone();
+        let mut last_block = iv;
+        Cbc::new(&mut last_block).encrypt(as_blocks_mut(&mut ciphertext).unwrap(), &cipher).unwrap();
+        for threads in [1, 2, 3, 4, 16] {
+            let mut decrypted = ciphertext.clone();
+            let mut chained_iv = iv;
+            let mut cbc = Cbc::new(&mut chained_iv);
+            cbc.decrypt_parallel(as_blocks_mut(&mut decrypted).unwrap(), &cipher, threads).unwrap();
+            assert_eq!(last_block, chained_iv);
+            assert_eq!(plaintext, decrypted);
+        }
+    }
 }
@@ -1,45 +1,80 @@
-use crate::{aes, Block, BLOCK_SIZE};
+use super::BlockMode;
+use crate::{Block, BlockCipher, CipherError};
 
-pub(crate) fn encrypt(plaintext: &[u8], key: &[u8]) -> Vec<u8> {
-    assert_eq!(
-        0,
-        plaintext.len() % BLOCK_SIZE,
-        "Input length is not a multiply of block size."
-    );
+/// Electronic codebook, every block is encrypted independently.
+pub(crate) struct Ecb;
 
-    let mut output = Vec::<u8>::with_capacity(plaintext.len());
-    for i in 0..(plaintext.len() / BLOCK_SIZE) {
-        let block: &Block = &plaintext[(i * BLOCK_SIZE)..((i + 1) * BLOCK_SIZE)]
-            .try_into()
-            .unwrap();
-        let encrypted_block = aes::encrypt_block(block, key);
-        output.extend(encrypted_block);
+impl BlockMode for Ecb {
+    // ECB has no state, so there is nothing to reset, but passing an IV is likely a mistake.
+    fn reset(&mut self, iv: &[u8]) -> Result<(), CipherError> {
+        if !iv.is_empty() {
+            return Err(CipherError::InvalidIvLength { len: iv.len() });
+        }
+        Ok(())
+    }
+
+    fn encrypt(
+        &mut self,
+        blocks: &mut [Block],
+        cipher: &dyn BlockCipher,
+    ) -> Result<(), CipherError> {
+        cipher.encrypt_blocks(blocks);
+        Ok(())
     }
-    output
-}
 
-pub(crate) fn decrypt(ciphertext: &[u8], key: &[u8]) -> Vec<u8> {
-    assert_eq!(
-        0,
-        ciphertext.len() % BLOCK_SIZE,
-        "Input length is not a multiply of block size."
-    );
+    fn decrypt(
+        &mut self,
+        blocks: &mut [Block],
+        cipher: &dyn BlockCipher,
+    ) -> Result<(), CipherError> {
+        cipher.decrypt_blocks(blocks);
+        Ok(())
+    }
 
-    let mut output = Vec::<u8>::with_capacity(ciphertext.len());
-    for i in 0..(ciphertext.len() / BLOCK_SIZE) {
-        let block: &Block = &ciphertext[(i * BLOCK_SIZE)..((i + 1) * BLOCK_SIZE)]
-            .try_into()
-            .unwrap();
-        let decrypted_block = aes::decrypt_block(block, key);
-        output.extend(decrypted_block);
+    #[cfg(feature = "std")]
+    fn encrypt_parallel(
+        &mut self,
+        blocks: &mut [Block],
+        cipher: &dyn BlockCipher,
+        threads: usize,
+    ) -> Result<(), CipherError> {
+        let chunk_blocks = super::chunk_blocks(blocks.len(), threads);
+        if chunk_blocks >= blocks.len() {
+            return self.encrypt(blocks, cipher);
+        }
+        std::thread::scope(|scope| {
+            for chunk in blocks.chunks_mut(chunk_blocks) {
+                scope.spawn(|| cipher.encrypt_blocks(chunk));
+            }
+        });
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    fn decrypt_parallel(
+        &mut self,
+        blocks: &mut [Block],
+        cipher: &dyn BlockCipher,
+        threads: usize,
+    ) -> Result<(), CipherError> {
+        let chunk_blocks = super::chunk_blocks(blocks.len(), threads);
+        if chunk_blocks >= blocks.len() {
+            return self.decrypt(blocks, cipher);
+        }
+        std::thread::scope(|scope| {
+            for chunk in blocks.chunks_mut(chunk_blocks) {
+                scope.spawn(|| cipher.decrypt_blocks(chunk));
+            }
+        });
+        Ok(())
     }
-    output
 }
 
 #[cfg(test)]
 #[rustfmt::skip]
 mod tests {
     use super::*;
+    use crate::{mode::as_blocks_mut, Aes128};
 
     #[test]
     fn test() {
@@ -55,11 +90,38 @@ mod tests {
             0x43, 0xb1, 0xcd, 0x7f, 0x59, 0x8e, 0xce, 0x23, 0x88, 0x1b, 0x00, 0xe3, 0xed, 0x03, 0x06, 0x88,
             0x7b, 0x0c, 0x78, 0x5e, 0x27, 0xe8, 0xad, 0x3f, 0x82, 0x23, 0x20, 0x71, 0x04, 0x72, 0x5d, 0xd4,
         ];
-        let key: &[u8] = &[
+        let key = &[
             0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c,
         ];
 
-        assert_eq!(ciphertext, encrypt(plaintext, key));
-        assert_eq!(plaintext, decrypt(ciphertext, key));
+        let cipher = Aes128::new(key);
+
+        let mut encrypted = plaintext.to_vec();
+        Ecb.encrypt(as_blocks_mut(&mut encrypted).unwrap(), &cipher).unwrap();
+        let mut decrypted = ciphertext.to_vec();
+        Ecb.decrypt(as_blocks_mut(&mut decrypted).unwrap(), &cipher).unwrap();
+
+        assert_eq!(ciphertext, encrypted);
+        assert_eq!(plaintext, decrypted);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn parallel_matches_serial() {
+        let cipher = Aes128::new(&[0x42; 16]);
+        let mut seed = 0x2545f4914f6cdd1d;
+        let mut plaintext = vec![0u8; 4 * 256 * crate::BLOCK_SIZE + 48];
+        crate::aes::tests::random_bytes(&mut seed, &mut plaintext);
+
+        let mut serial = plaintext.clone();
+        Ecb.encrypt(as_blocks_mut(&mut serial).unwrap(), &cipher).unwrap();
+        for threads in [1, 2, 3, 4, 16] {
+            let mut parallel = plaintext.clone();
+            Ecb.encrypt_parallel(as_blocks_mut(&mut parallel).unwrap(), &cipher, threads).unwrap();
+            assert_eq!(serial, parallel);
+
+            Ecb.decrypt_parallel(as_blocks_mut(&mut parallel).unwrap(), &cipher, threads).unwrap();
+            assert_eq!(plaintext, parallel);
+        }
     }
 }
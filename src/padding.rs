@@ -1,11 +1,20 @@
+mod no_padding;
 mod pkcs7;
 
-#[derive(Debug, PartialEq)]
-pub(crate) struct PaddingError(pub(crate) String);
+use crate::CipherError;
 
-pub(crate) trait Padder {
-    fn pad(&self, input: &[u8]) -> Result<Vec<u8>, PaddingError>;
-    fn unpad<'a>(&self, input: &'a [u8]) -> Result<&'a [u8], PaddingError>;
+/// Padding scheme used to extend the last, incomplete block of the input to the block size.
+///
+/// [`Cipher`](crate::Cipher) calls `pad` once on finalization of the encryption with the bytes
+/// left over from the updates (always less than one block). Padded input is written to `output`,
+/// which has room for one block, and its length is returned. It has to be exactly one block,
+/// longer padding couldn't be removed, as on finalization of the decryption `unpad` receives
+/// only the last decrypted block and returns the plaintext part of it. Malformed padding should
+/// be reported as [`CipherError::InvalidPadding`].
+pub trait Padder {
+    fn pad(&self, input: &[u8], output: &mut [u8]) -> Result<usize, CipherError>;
+    fn unpad<'a>(&self, input: &'a [u8]) -> Result<&'a [u8], CipherError>;
 }
 
+pub(crate) use no_padding::NoPadding;
 pub(crate) use pkcs7::PKCS7;
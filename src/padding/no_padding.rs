@@ -0,0 +1,18 @@
+use super::Padder;
+use crate::CipherError;
+
+/// Leaves the input as is, so its length has to be a multiple of the block size.
+pub(crate) struct NoPadding;
+
+impl Padder for NoPadding {
+    fn pad(&self, input: &[u8], _output: &mut [u8]) -> Result<usize, CipherError> {
+        if !input.is_empty() {
+            return Err(CipherError::InvalidPadding);
+        }
+        Ok(0)
+    }
+
+    fn unpad<'a>(&self, input: &'a [u8]) -> Result<&'a [u8], CipherError> {
+        Ok(input)
+    }
+}
@@ -1,46 +1,46 @@
-use super::{Padder, PaddingError};
+use super::Padder;
+use crate::CipherError;
 
 pub(crate) struct PKCS7 {
     size: usize,
 }
 
 impl PKCS7 {
-    pub(crate) fn new(size: usize) -> Self {
+    pub(crate) const fn new(size: usize) -> Self {
         Self { size }
     }
 }
 
 impl Padder for PKCS7 {
-    fn pad(&self, input: &[u8]) -> Result<Vec<u8>, PaddingError> {
+    fn pad(&self, input: &[u8], output: &mut [u8]) -> Result<usize, CipherError> {
         if input.len() > self.size {
-            return Err(PaddingError(format!(
-                "Input size ({}) exceeds expected padder size ({}).",
-                input.len(),
-                self.size
-            )));
+            return Err(CipherError::InvalidPadding);
+        }
+        if output.len() < self.size {
+            return Err(CipherError::OutputTooSmall {
+                required: self.size,
+                len: output.len(),
+            });
         }
         let pad_len = self.size - input.len();
-        let mut output = Vec::<u8>::with_capacity(self.size);
-        output.extend(input);
-        (0..(pad_len)).for_each(|_| output.push(pad_len as u8));
-        Ok(output)
+        output[..input.len()].copy_from_slice(input);
+        output[input.len()..self.size].fill(pad_len as u8);
+        Ok(self.size)
     }
 
-    fn unpad<'a>(&self, input: &'a [u8]) -> Result<&'a [u8], PaddingError> {
+    fn unpad<'a>(&self, input: &'a [u8]) -> Result<&'a [u8], CipherError> {
         if input.len() != self.size {
-            return Err(PaddingError(format!(
-                "Input size ({}) is not equal to padder size ({}).",
-                input.len(),
-                self.size
-            )));
+            return Err(CipherError::InvalidPadding);
         }
         let pad_len = input[input.len() - 1];
+        if pad_len == 0 || pad_len as usize > self.size {
+            return Err(CipherError::InvalidPadding);
+        }
         let pad_start = input.len() - pad_len as usize;
-        if input[pad_start..].iter().all(|x| *x == pad_len) {
-            Ok(&input[..pad_start])
-        } else {
-            Ok(input)
+        if input[pad_start..].iter().any(|x| *x != pad_len) {
+            return Err(CipherError::InvalidPadding);
         }
+        Ok(&input[..pad_start])
     }
 }
 
@@ -52,10 +52,20 @@ mod tests {
     fn should_err_pad_when_input_to_big() {
         let padder = PKCS7::new(16);
         let input = [0u8; 17];
-        let expected_result = Err(PaddingError(String::from(
-            "Input size (17) exceeds expected padder size (16).",
-        )));
-        let result = padder.pad(&input);
+        let expected_result = Err(CipherError::InvalidPadding);
+        let result = padder.pad(&input, &mut [0u8; 32]);
+        assert_eq!(expected_result, result);
+    }
+
+    #[test]
+    fn should_err_pad_when_output_to_small() {
+        let padder = PKCS7::new(16);
+        let input = [0u8; 4];
+        let expected_result = Err(CipherError::OutputTooSmall {
+            required: 16,
+            len: 15,
+        });
+        let result = padder.pad(&input, &mut [0u8; 15]);
         assert_eq!(expected_result, result);
     }
 
@@ -63,47 +73,58 @@ mod tests {
     fn should_pad_0() {
         let padder = PKCS7::new(16);
         let input = [0u8; 16];
-        let expected_output = vec![0u8; 16];
-        let padded_input = padder.pad(&input);
-        assert_eq!(Ok(expected_output), padded_input);
+        let expected_output = [0u8; 16];
+        let mut output = [0xffu8; 16];
+        let padded_input = padder.pad(&input, &mut output);
+        assert_eq!(Ok(16), padded_input);
+        assert_eq!(expected_output, output);
     }
 
     #[test]
     fn should_pad_4() {
         let padder = PKCS7::new(16);
         let input = [0u8; 12];
-        let expected_output: Vec<u8> = vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 4, 4, 4];
-        let padded_input = padder.pad(&input);
-        assert_eq!(Ok(expected_output), padded_input);
+        let expected_output = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 4, 4, 4];
+        let mut output = [0u8; 32];
+        let padded_input = padder.pad(&input, &mut output);
+        assert_eq!(Ok(16), padded_input);
+        assert_eq!(expected_output, output[..16]);
     }
 
     #[test]
     fn should_pad_16() {
         let padder = PKCS7::new(16);
         let input = [16u8; 16];
-        let expected_output = vec![16u8; 16];
-        let unpadded_input = padder.pad(&input);
-        assert_eq!(Ok(expected_output), unpadded_input);
+        let expected_output = [16u8; 16];
+        let mut output = [0u8; 16];
+        let padded_input = padder.pad(&input, &mut output);
+        assert_eq!(Ok(16), padded_input);
+        assert_eq!(expected_output, output);
     }
 
     #[test]
     fn should_err_unpad_when_input_len_does_not_match() {
         let padder = PKCS7::new(16);
         let input = [0u8; 17];
-        let expected_result = Err(PaddingError(String::from(
-            "Input size (17) is not equal to padder size (16).",
-        )));
+        let expected_result = Err(CipherError::InvalidPadding);
         let result = padder.unpad(&input);
         assert_eq!(expected_result, result);
     }
 
     #[test]
-    fn should_unpad_0() {
+    fn should_err_unpad_0() {
         let padder = PKCS7::new(16);
         let input = [0u8; 16];
-        let expected_output: &[u8] = &[0u8; 16];
         let unpadded_input = padder.unpad(&input);
-        assert_eq!(Ok(expected_output), unpadded_input);
+        assert_eq!(Err(CipherError::InvalidPadding), unpadded_input);
+    }
+
+    #[test]
+    fn should_err_unpad_when_pad_bytes_differ() {
+        let padder = PKCS7::new(16);
+        let input = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 3, 4, 4];
+        let unpadded_input = padder.unpad(&input);
+        assert_eq!(Err(CipherError::InvalidPadding), unpadded_input);
     }
 
     #[test]
@@ -123,4 +144,12 @@ mod tests {
         let unpadded_input = padder.unpad(&input);
         assert_eq!(Ok(expected_output), unpadded_input);
     }
+
+    #[test]
+    fn should_err_unpad_when_pad_len_exceeds_size() {
+        let padder = PKCS7::new(16);
+        let input = [17u8; 16];
+        let result = padder.unpad(&input);
+        assert_eq!(Err(CipherError::InvalidPadding), result);
+    }
 }
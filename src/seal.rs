@@ -0,0 +1,54 @@
+// One-shot CBC encryption with a random IV stored in front of the ciphertext.
+//
+// Output is not authenticated: it can be modified without detection, and distinguishable
+// errors of `open` (padding vs. success) make it a padding oracle. Add a MAC when messages
+// come from untrusted storage or are decrypted on behalf of others.
+use alloc::vec::Vec;
+
+use crate::{
+    entropy::EntropySource, Block, BlockCipher, Cipher, CipherError, Mode, Operation, Padding,
+    BLOCK_SIZE,
+};
+
+/// Encrypts `plaintext` with CBC and PKCS7 under a fresh IV read from the [`OsEntropy`](crate::OsEntropy).
+/// Output is the IV followed by the ciphertext, [`open`] reverses it. Requires `std`.
+///
+/// Output is only encrypted, not authenticated, so it is malleable, see [`open`].
+#[cfg(feature = "std")]
+pub fn seal(key: &dyn BlockCipher, plaintext: &[u8]) -> Result<Vec<u8>, CipherError> {
+    seal_with(key, plaintext, &mut crate::OsEntropy)
+}
+
+/// Same as [`seal`], but the IV is read from the given `entropy` source.
+pub fn seal_with(
+    key: &dyn BlockCipher,
+    plaintext: &[u8],
+    entropy: &mut dyn EntropySource,
+) -> Result<Vec<u8>, CipherError> {
+    let mut iv: Block = [0u8; BLOCK_SIZE];
+    entropy.fill(&mut iv)?;
+    let mut cipher = Cipher::init(Operation::Encrypt, key, Mode::Cbc(iv), Padding::PKCS7)?;
+
+    let mut sealed =
+        Vec::with_capacity(BLOCK_SIZE + cipher.update_len(plaintext.len()) + BLOCK_SIZE);
+    sealed.extend_from_slice(&iv);
+    sealed.extend(cipher.update(plaintext)?);
+    sealed.extend(cipher.finalize()?);
+    Ok(sealed)
+}
+
+/// Decrypts the output of [`seal`], the IV is taken from the first block.
+///
+/// Modified messages are not detected, they decrypt to garbage or fail the padding check.
+/// Errors must not be exposed to whoever supplied the message, they make a padding oracle
+/// which reveals the plaintext.
+pub fn open(key: &dyn BlockCipher, sealed: &[u8]) -> Result<Vec<u8>, CipherError> {
+    if sealed.len() < BLOCK_SIZE {
+        return Err(CipherError::TruncatedInput);
+    }
+    let (iv, ciphertext) = sealed.split_at(BLOCK_SIZE);
+    let mut cipher = Cipher::init(Operation::Decrypt, key, Mode::cbc(iv)?, Padding::PKCS7)?;
+    let mut plaintext = cipher.update(ciphertext)?;
+    plaintext.extend(cipher.finalize()?);
+    Ok(plaintext)
+}
@@ -0,0 +1,184 @@
+// Adapters of `Cipher` to `std::io` streams. Data is processed in chunks of fixed size,
+// so memory usage doesn't depend on the length of the stream.
+use std::io::{self, Read, Write};
+use std::ops::Range;
+
+use crate::{
+    zeroize::zeroize, BlockCipher, Cipher, CipherError, Key, Mode, Operation, Padding, BLOCK_SIZE,
+};
+
+/// Maximum number of input bytes passed to the cipher at once.
+const CHUNK_SIZE: usize = 8 * 1024;
+/// Fits the output of an update with a whole chunk and of the finalization.
+const OUTPUT_SIZE: usize = CHUNK_SIZE + BLOCK_SIZE;
+
+/// Encrypts data written to it and writes the ciphertext to the inner writer.
+/// Incomplete block is buffered until more data is written, [`EncryptingWriter::finish`]
+/// pads it and has to be called to write the end of the ciphertext. Dropping the writer
+/// without `finish` silently loses the padded final block, the ciphertext is incomplete.
+pub struct EncryptingWriter<'a, W: Write> {
+    inner: W,
+    cipher: Cipher<'a>,
+    output: Box<[u8; OUTPUT_SIZE]>,
+    pending: Range<usize>,
+}
+
+impl<'a, W: Write> EncryptingWriter<'a, W> {
+    pub fn new(
+        inner: W,
+        key: &'a dyn BlockCipher,
+        mode: Mode,
+        padding: Padding,
+    ) -> Result<Self, CipherError> {
+        let cipher = Cipher::init(Operation::Encrypt, key, mode, padding)?;
+        Ok(Self::from_cipher(inner, cipher))
+    }
+
+    fn from_cipher(inner: W, cipher: Cipher<'a>) -> Self {
+        Self {
+            inner,
+            cipher,
+            output: Box::new([0u8; OUTPUT_SIZE]),
+            pending: 0..0,
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Pads and encrypts the last block, writes everything to the inner writer
+    /// and flushes it. Returns the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_pending()?;
+        let len = self.cipher.finalize_into(&mut self.output[..])?;
+        self.pending = 0..len;
+        self.write_pending()?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    // Ciphertext is kept until the inner writer accepts it, so no data is lost
+    // when writing fails with a recoverable error.
+    fn write_pending(&mut self) -> io::Result<()> {
+        while !self.pending.is_empty() {
+            let written = self.inner.write(&self.output[self.pending.clone()])?;
+            if written == 0 {
+                return Err(io::ErrorKind::WriteZero.into());
+            }
+            self.pending.start += written;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> EncryptingWriter<'static, W> {
+    /// Same as [`EncryptingWriter::new`], but owns the expanded key, see [`Cipher::with_key`].
+    pub fn with_key(
+        inner: W,
+        key: &Key,
+        mode: Mode,
+        padding: Padding,
+    ) -> Result<Self, CipherError> {
+        let cipher = Cipher::with_key(Operation::Encrypt, key, mode, padding)?;
+        Ok(Self::from_cipher(inner, cipher))
+    }
+}
+
+impl<'a, W: Write> Write for EncryptingWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_pending()?;
+        let len = buf.len().min(CHUNK_SIZE);
+        let output_len = self.cipher.update_into(&buf[..len], &mut self.output[..])?;
+        self.pending = 0..output_len;
+        Ok(len)
+    }
+
+    /// Writes all complete blocks to the inner writer and flushes it.
+    /// Incomplete block stays buffered until more data is written or the writer is finished.
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_pending()?;
+        self.inner.flush()
+    }
+}
+
+/// Reads ciphertext from the inner reader and decrypts it. Last block is held back
+/// until the end of the inner stream, then its padding is removed.
+/// Malformed ciphertext is reported as [`io::ErrorKind::InvalidData`].
+pub struct DecryptingReader<'a, R: Read> {
+    inner: R,
+    cipher: Cipher<'a>,
+    finished: bool,
+    input: Box<[u8; CHUNK_SIZE]>,
+    output: Box<[u8; OUTPUT_SIZE]>,
+    pending: Range<usize>,
+}
+
+impl<'a, R: Read> DecryptingReader<'a, R> {
+    pub fn new(
+        inner: R,
+        key: &'a dyn BlockCipher,
+        mode: Mode,
+        padding: Padding,
+    ) -> Result<Self, CipherError> {
+        let cipher = Cipher::init(Operation::Decrypt, key, mode, padding)?;
+        Ok(Self::from_cipher(inner, cipher))
+    }
+
+    fn from_cipher(inner: R, cipher: Cipher<'a>) -> Self {
+        Self {
+            inner,
+            cipher,
+            finished: false,
+            input: Box::new([0u8; CHUNK_SIZE]),
+            output: Box::new([0u8; OUTPUT_SIZE]),
+            pending: 0..0,
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+}
+
+impl<R: Read> DecryptingReader<'static, R> {
+    /// Same as [`DecryptingReader::new`], but owns the expanded key, see [`Cipher::with_key`].
+    pub fn with_key(
+        inner: R,
+        key: &Key,
+        mode: Mode,
+        padding: Padding,
+    ) -> Result<Self, CipherError> {
+        let cipher = Cipher::with_key(Operation::Decrypt, key, mode, padding)?;
+        Ok(Self::from_cipher(inner, cipher))
+    }
+}
+
+impl<'a, R: Read> Read for DecryptingReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pending.is_empty() && !self.finished && !buf.is_empty() {
+            let input_len = self.inner.read(&mut self.input[..])?;
+            let output_len = if input_len == 0 {
+                let len = self.cipher.finalize_into(&mut self.output[..])?;
+                self.finished = true;
+                len
+            } else {
+                self.cipher
+                    .update_into(&self.input[..input_len], &mut self.output[..])?
+            };
+            self.pending = 0..output_len;
+        }
+
+        let len = buf.len().min(self.pending.len());
+        let start = self.pending.start;
+        buf[..len].copy_from_slice(&self.output[start..start + len]);
+        self.pending.start += len;
+        Ok(len)
+    }
+}
+
+impl<'a, R: Read> Drop for DecryptingReader<'a, R> {
+    fn drop(&mut self) {
+        zeroize(&mut *self.output);
+    }
+}
@@ -0,0 +1,277 @@
+// JCE style names of the cipher configuration, "algorithm/mode/padding".
+use core::{fmt, str::FromStr};
+
+#[cfg(feature = "alloc")]
+use crate::{Cipher, Operation};
+use crate::{CipherError, Key, Mode, Padding};
+
+const ALGORITHMS: &[(&str, Option<usize>)] = &[
+    ("AES", None),
+    ("AES_128", Some(128)),
+    ("AES_192", Some(192)),
+    ("AES_256", Some(256)),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ModeName {
+    Ecb,
+    Cbc,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PaddingName {
+    Pkcs5,
+    None,
+}
+
+/// Cipher configuration parsed from a JCE transformation string such as
+/// `AES/CBC/PKCS5Padding` or `AES_256/ECB/NoPadding`. Names are case insensitive,
+/// `PKCS7Padding` is accepted as an alias of `PKCS5Padding` and bare `AES` means
+/// `AES/ECB/PKCS5Padding`. [`Display`](fmt::Display) writes the canonical string.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transformation {
+    key_bits: Option<usize>,
+    mode: ModeName,
+    padding: PaddingName,
+}
+
+impl Transformation {
+    pub fn parse(transformation: &str) -> Result<Self, CipherError> {
+        let mut parts = transformation.split('/');
+        let algorithm = parts.next().unwrap_or_default();
+        let key_bits = ALGORITHMS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(algorithm))
+            .map(|&(_, bits)| bits)
+            .ok_or(CipherError::InvalidTransformation {
+                reason: "unknown algorithm, expected AES, AES_128, AES_192 or AES_256",
+            })?;
+
+        let (mode, padding) = match (parts.next(), parts.next(), parts.next()) {
+            (None, _, _) => ("ECB", "PKCS5Padding"),
+            (Some(mode), Some(padding), None) => (mode, padding),
+            _ => {
+                return Err(CipherError::InvalidTransformation {
+                    reason: "expected algorithm/mode/padding",
+                })
+            }
+        };
+        let mode = if mode.eq_ignore_ascii_case("ECB") {
+            ModeName::Ecb
+        } else if mode.eq_ignore_ascii_case("CBC") {
+            ModeName::Cbc
+        } else {
+            return Err(CipherError::InvalidTransformation {
+                reason: "unsupported mode, expected ECB or CBC",
+            });
+        };
+        let padding = if padding.eq_ignore_ascii_case("PKCS5Padding")
+            || padding.eq_ignore_ascii_case("PKCS7Padding")
+        {
+            PaddingName::Pkcs5
+        } else if padding.eq_ignore_ascii_case("NoPadding") {
+            PaddingName::None
+        } else {
+            return Err(CipherError::InvalidTransformation {
+                reason: "unsupported padding, expected PKCS5Padding, PKCS7Padding or NoPadding",
+            });
+        };
+
+        Ok(Self {
+            key_bits,
+            mode,
+            padding,
+        })
+    }
+
+    /// Key length required by the algorithm name, `None` for plain `AES`.
+    pub fn key_bits(&self) -> Option<usize> {
+        self.key_bits
+    }
+
+    /// Same transformation with the key length in the algorithm name, e.g. `AES_256/...`.
+    pub fn with_key_bits(self, bits: usize) -> Result<Self, CipherError> {
+        if !matches!(bits, 128 | 192 | 256) {
+            return Err(CipherError::InvalidKeyLength { bits });
+        }
+        Ok(Self {
+            key_bits: Some(bits),
+            ..self
+        })
+    }
+
+    /// Mode with the initialization vector, which has to be empty for ECB.
+    pub fn mode(&self, iv: &[u8]) -> Result<Mode, CipherError> {
+        match self.mode {
+            ModeName::Ecb if iv.is_empty() => Ok(Mode::Ecb),
+            ModeName::Ecb => Err(CipherError::InvalidIvLength { len: iv.len() }),
+            ModeName::Cbc => Mode::cbc(iv),
+        }
+    }
+
+    pub fn padding(&self) -> Padding {
+        match self.padding {
+            PaddingName::Pkcs5 => Padding::PKCS7,
+            PaddingName::None => Padding::NoPadding,
+        }
+    }
+
+    /// Checks that the key length matches the one required by the algorithm name.
+    pub fn check_key(&self, key: &Key) -> Result<(), CipherError> {
+        match self.key_bits {
+            Some(expected) if expected != key.bits() => Err(CipherError::KeySizeMismatch {
+                expected,
+                actual: key.bits(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Creates a cipher configured by the transformation, see [`Cipher::with_key`].
+    #[cfg(feature = "alloc")]
+    pub fn cipher(
+        &self,
+        operation: Operation,
+        key: &Key,
+        iv: &[u8],
+    ) -> Result<Cipher<'static>, CipherError> {
+        self.check_key(key)?;
+        Cipher::with_key(operation, key, self.mode(iv)?, self.padding())
+    }
+
+    pub(crate) fn from_parts(mode: &Mode, padding: &Padding) -> Result<Self, CipherError> {
+        let mode = match mode {
+            Mode::Ecb => ModeName::Ecb,
+            Mode::Cbc(_) => ModeName::Cbc,
+            #[cfg(feature = "alloc")]
+            Mode::Custom(_) => {
+                return Err(CipherError::InvalidTransformation {
+                    reason: "custom mode has no standard name",
+                })
+            }
+        };
+        let padding = match padding {
+            Padding::PKCS7 => PaddingName::Pkcs5,
+            Padding::NoPadding => PaddingName::None,
+            #[cfg(feature = "alloc")]
+            Padding::Custom(_) => {
+                return Err(CipherError::InvalidTransformation {
+                    reason: "custom padding has no standard name",
+                })
+            }
+        };
+        Ok(Self {
+            key_bits: None,
+            mode,
+            padding,
+        })
+    }
+}
+
+impl FromStr for Transformation {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Transformation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let algorithm = ALGORITHMS
+            .iter()
+            .find(|(_, bits)| *bits == self.key_bits)
+            .map_or("AES", |(name, _)| name);
+        let mode = match self.mode {
+            ModeName::Ecb => "ECB",
+            ModeName::Cbc => "CBC",
+        };
+        let padding = match self.padding {
+            PaddingName::Pkcs5 => "PKCS5Padding",
+            PaddingName::None => "NoPadding",
+        };
+        write!(f, "{algorithm}/{mode}/{padding}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_format() {
+        let cases = [
+            ("AES/CBC/PKCS5Padding", "AES/CBC/PKCS5Padding", None),
+            ("aes/ecb/pkcs7padding", "AES/ECB/PKCS5Padding", None),
+            ("AES_128/CBC/NoPadding", "AES_128/CBC/NoPadding", Some(128)),
+            (
+                "AES_192/ECB/PKCS5Padding",
+                "AES_192/ECB/PKCS5Padding",
+                Some(192),
+            ),
+            ("Aes_256/Cbc/NoPadding", "AES_256/CBC/NoPadding", Some(256)),
+            ("AES", "AES/ECB/PKCS5Padding", None),
+        ];
+        for (input, canonical, key_bits) in cases {
+            let transformation: Transformation = input.parse().unwrap();
+            assert_eq!(canonical, transformation.to_string());
+            assert_eq!(key_bits, transformation.key_bits());
+            assert_eq!(Ok(transformation), canonical.parse());
+        }
+    }
+
+    #[test]
+    fn unsupported_transformations_are_rejected() {
+        let cases = [
+            ("", "unknown algorithm"),
+            ("DES/CBC/PKCS5Padding", "unknown algorithm"),
+            ("AES_512/CBC/PKCS5Padding", "unknown algorithm"),
+            ("AES/CBC", "expected algorithm/mode/padding"),
+            ("AES/CBC/PKCS5Padding/", "expected algorithm/mode/padding"),
+            ("AES/GCM/NoPadding", "unsupported mode"),
+            ("AES/CBC/ISO10126Padding", "unsupported padding"),
+        ];
+        for (input, expected) in cases {
+            match Transformation::parse(input) {
+                Err(CipherError::InvalidTransformation { reason }) => {
+                    assert!(reason.starts_with(expected), "{input}: {reason}")
+                }
+                other => panic!("{input}: {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn mode_and_key_are_checked() {
+        let cbc = Transformation::parse("AES_256/CBC/NoPadding").unwrap();
+        assert!(matches!(cbc.mode(&[7; 16]), Ok(Mode::Cbc(iv)) if iv == [7; 16]));
+        assert!(matches!(
+            cbc.mode(&[]),
+            Err(CipherError::InvalidIvLength { len: 0 })
+        ));
+        assert!(matches!(cbc.padding(), Padding::NoPadding));
+        assert_eq!(Ok(()), cbc.check_key(&Key::new(&[0; 32]).unwrap()));
+        assert_eq!(
+            Err(CipherError::KeySizeMismatch {
+                expected: 256,
+                actual: 128
+            }),
+            cbc.check_key(&Key::new(&[0; 16]).unwrap())
+        );
+
+        let ecb = Transformation::parse("AES/ECB/PKCS5Padding").unwrap();
+        assert!(matches!(
+            ecb.mode(&[0; 16]),
+            Err(CipherError::InvalidIvLength { len: 16 })
+        ));
+        assert_eq!(Ok(()), ecb.check_key(&Key::new(&[0; 24]).unwrap()));
+        assert_eq!(
+            "AES_192/ECB/PKCS5Padding",
+            ecb.with_key_bits(192).unwrap().to_string()
+        );
+        assert_eq!(
+            Err(CipherError::InvalidKeyLength { bits: 64 }),
+            ecb.with_key_bits(64)
+        );
+    }
+}
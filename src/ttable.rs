@@ -0,0 +1,290 @@
+// T-table implementation of the round function.
+// SubBytes, ShiftRows and MixColumns are fused into four 32-bit lookups per column.
+// https://csrc.nist.gov/csrc/media/projects/cryptographic-standards-and-guidelines/documents/aes-development/rijndael-ammended.pdf (section 5.2.1)
+use crate::{
+    key::{self, MAX_KEY_SCHEDULE_LEN},
+    lookup::{
+        INV_S_BOX, MULT_11_LOOKUP, MULT_13_LOOKUP, MULT_14_LOOKUP, MULT_2_LOOKUP, MULT_3_LOOKUP,
+        MULT_9_LOOKUP, S_BOX,
+    },
+    zeroize::zeroize,
+    Block, NB,
+};
+
+static TE0: [u32; 256] = enc_table(0);
+static TE1: [u32; 256] = enc_table(8);
+static TE2: [u32; 256] = enc_table(16);
+static TE3: [u32; 256] = enc_table(24);
+
+static TD0: [u32; 256] = dec_table(0);
+static TD1: [u32; 256] = dec_table(8);
+static TD2: [u32; 256] = dec_table(16);
+static TD3: [u32; 256] = dec_table(24);
+
+// Column of MixColumns applied to the substituted byte: [2•s, s, s, 3•s].
+const fn enc_table(rotation: u32) -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let s = S_BOX[i];
+        let column = [MULT_2_LOOKUP[s as usize], s, s, MULT_3_LOOKUP[s as usize]];
+        table[i] = u32::from_be_bytes(column).rotate_right(rotation);
+        i += 1;
+    }
+    table
+}
+
+// Column of InvMixColumns applied to the inverse substituted byte: [14•s, 9•s, 13•s, 11•s].
+const fn dec_table(rotation: u32) -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let s = INV_S_BOX[i] as usize;
+        let column = [
+            MULT_14_LOOKUP[s],
+            MULT_9_LOOKUP[s],
+            MULT_13_LOOKUP[s],
+            MULT_11_LOOKUP[s],
+        ];
+        table[i] = u32::from_be_bytes(column).rotate_right(rotation);
+        i += 1;
+    }
+    table
+}
+
+/// Round keys packed into big-endian column words.
+/// Decryption round keys have InvMixColumns applied (equivalent inverse cipher).
+pub(crate) struct KeySchedule {
+    nr: u8,
+    round_keys: [u32; MAX_KEY_SCHEDULE_LEN],
+    dec_round_keys: [u32; MAX_KEY_SCHEDULE_LEN],
+}
+
+impl KeySchedule {
+    pub(crate) fn new(key_schedule: &key::KeySchedule) -> Self {
+        let nr = key_schedule.nr();
+
+        let mut round_keys = [0u32; MAX_KEY_SCHEDULE_LEN];
+        for (rk, word) in round_keys.iter_mut().zip(key_schedule.round_keys()) {
+            *rk = u32::from_be_bytes(*word);
+        }
+
+        let mut dec_round_keys = [0u32; MAX_KEY_SCHEDULE_LEN];
+        for (rk, word) in dec_round_keys.iter_mut().zip(key_schedule.dec_round_keys()) {
+            *rk = u32::from_be_bytes(*word);
+        }
+
+        Self {
+            nr,
+            round_keys,
+            dec_round_keys,
+        }
+    }
+}
+
+impl Drop for KeySchedule {
+    fn drop(&mut self) {
+        zeroize(&mut self.round_keys);
+        zeroize(&mut self.dec_round_keys);
+    }
+}
+
+/// Number of blocks whose rounds are interleaved by `encrypt_blocks` and `decrypt_blocks`.
+const LANES: usize = 4;
+
+pub(crate) fn encrypt_block(plaintext: &Block, key_schedule: &KeySchedule) -> Block {
+    let nr = key_schedule.nr as usize;
+    let rk = &key_schedule.round_keys;
+    let mut s = load(plaintext, &rk[0..NB]);
+
+    for round in 1..nr {
+        s = enc_round(&s, &rk[round * NB..(round + 1) * NB]);
+    }
+
+    let ciphertext = store(&enc_last_round(&s, &rk[nr * NB..(nr + 1) * NB]));
+    zeroize(&mut s);
+    ciphertext
+}
+
+pub(crate) fn decrypt_block(ciphertext: &Block, key_schedule: &KeySchedule) -> Block {
+    let nr = key_schedule.nr as usize;
+    let rk = &key_schedule.dec_round_keys;
+    let mut s = load(ciphertext, &rk[0..NB]);
+
+    for round in 1..nr {
+        s = dec_round(&s, &rk[round * NB..(round + 1) * NB]);
+    }
+
+    let plaintext = store(&dec_last_round(&s, &rk[nr * NB..(nr + 1) * NB]));
+    zeroize(&mut s);
+    plaintext
+}
+
+/// Encrypts `LANES` blocks at once, so lookups of independent blocks can overlap.
+pub(crate) fn encrypt_blocks(blocks: &mut [Block], key_schedule: &KeySchedule) {
+    let nr = key_schedule.nr as usize;
+    let rk = &key_schedule.round_keys;
+
+    let mut chunks = blocks.chunks_exact_mut(LANES);
+    for chunk in &mut chunks {
+        let mut s = [[0u32; NB]; LANES];
+        for (s, block) in s.iter_mut().zip(chunk.iter()) {
+            *s = load(block, &rk[0..NB]);
+        }
+        for round in 1..nr {
+            let k = &rk[round * NB..(round + 1) * NB];
+            for s in s.iter_mut() {
+                *s = enc_round(s, k);
+            }
+        }
+        let k = &rk[nr * NB..(nr + 1) * NB];
+        for (s, block) in s.iter().zip(chunk.iter_mut()) {
+            *block = store(&enc_last_round(s, k));
+        }
+        zeroize(&mut s);
+    }
+    for block in chunks.into_remainder() {
+        *block = encrypt_block(block, key_schedule);
+    }
+}
+
+/// Decrypts `LANES` blocks at once, so lookups of independent blocks can overlap.
+pub(crate) fn decrypt_blocks(blocks: &mut [Block], key_schedule: &KeySchedule) {
+    let nr = key_schedule.nr as usize;
+    let rk = &key_schedule.dec_round_keys;
+
+    let mut chunks = blocks.chunks_exact_mut(LANES);
+    for chunk in &mut chunks {
+        let mut s = [[0u32; NB]; LANES];
+        for (s, block) in s.iter_mut().zip(chunk.iter()) {
+            *s = load(block, &rk[0..NB]);
+        }
+        for round in 1..nr {
+            let k = &rk[round * NB..(round + 1) * NB];
+            for s in s.iter_mut() {
+                *s = dec_round(s, k);
+            }
+        }
+        let k = &rk[nr * NB..(nr + 1) * NB];
+        for (s, block) in s.iter().zip(chunk.iter_mut()) {
+            *block = store(&dec_last_round(s, k));
+        }
+        zeroize(&mut s);
+    }
+    for block in chunks.into_remainder() {
+        *block = decrypt_block(block, key_schedule);
+    }
+}
+
+#[inline(always)]
+fn enc_round(s: &[u32; NB], k: &[u32]) -> [u32; NB] {
+    [
+        te(s[0], s[1], s[2], s[3]) ^ k[0],
+        te(s[1], s[2], s[3], s[0]) ^ k[1],
+        te(s[2], s[3], s[0], s[1]) ^ k[2],
+        te(s[3], s[0], s[1], s[2]) ^ k[3],
+    ]
+}
+
+#[inline(always)]
+fn enc_last_round(s: &[u32; NB], k: &[u32]) -> [u32; NB] {
+    [
+        sub(S_BOX, s[0], s[1], s[2], s[3]) ^ k[0],
+        sub(S_BOX, s[1], s[2], s[3], s[0]) ^ k[1],
+        sub(S_BOX, s[2], s[3], s[0], s[1]) ^ k[2],
+        sub(S_BOX, s[3], s[0], s[1], s[2]) ^ k[3],
+    ]
+}
+
+#[inline(always)]
+fn dec_round(s: &[u32; NB], k: &[u32]) -> [u32; NB] {
+    [
+        td(s[0], s[3], s[2], s[1]) ^ k[0],
+        td(s[1], s[0], s[3], s[2]) ^ k[1],
+        td(s[2], s[1], s[0], s[3]) ^ k[2],
+        td(s[3], s[2], s[1], s[0]) ^ k[3],
+    ]
+}
+
+#[inline(always)]
+fn dec_last_round(s: &[u32; NB], k: &[u32]) -> [u32; NB] {
+    [
+        sub(INV_S_BOX, s[0], s[3], s[2], s[1]) ^ k[0],
+        sub(INV_S_BOX, s[1], s[0], s[3], s[2]) ^ k[1],
+        sub(INV_S_BOX, s[2], s[1], s[0], s[3]) ^ k[2],
+        sub(INV_S_BOX, s[3], s[2], s[1], s[0]) ^ k[3],
+    ]
+}
+
+fn load(input: &Block, k: &[u32]) -> [u32; NB] {
+    let mut s = [0u32; NB];
+    for c in 0..NB {
+        let column = [
+            input[4 * c],
+            input[4 * c + 1],
+            input[4 * c + 2],
+            input[4 * c + 3],
+        ];
+        s[c] = u32::from_be_bytes(column) ^ k[c];
+    }
+    s
+}
+
+fn store(s: &[u32; NB]) -> Block {
+    let mut output = [0u8; 16];
+    for c in 0..NB {
+        output[4 * c..4 * (c + 1)].copy_from_slice(&s[c].to_be_bytes());
+    }
+    output
+}
+
+// Each argument provides one row of the output column, already shifted by ShiftRows.
+#[inline(always)]
+fn te(a: u32, b: u32, c: u32, d: u32) -> u32 {
+    TE0[(a >> 24) as usize]
+        ^ TE1[((b >> 16) & 0xff) as usize]
+        ^ TE2[((c >> 8) & 0xff) as usize]
+        ^ TE3[(d & 0xff) as usize]
+}
+
+#[inline(always)]
+fn td(a: u32, b: u32, c: u32, d: u32) -> u32 {
+    TD0[(a >> 24) as usize]
+        ^ TD1[((b >> 16) & 0xff) as usize]
+        ^ TD2[((c >> 8) & 0xff) as usize]
+        ^ TD3[(d & 0xff) as usize]
+}
+
+#[inline(always)]
+fn sub(s_box: &[u8], a: u32, b: u32, c: u32, d: u32) -> u32 {
+    u32::from_be_bytes([
+        s_box[(a >> 24) as usize],
+        s_box[((b >> 16) & 0xff) as usize],
+        s_box[((c >> 8) & 0xff) as usize],
+        s_box[(d & 0xff) as usize],
+    ])
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod tests {
+    use super::*;
+    use crate::{enc, state};
+
+    #[test]
+    fn enc_round_matches_reference() {
+        // Round keys of zero leave only SubBytes, ShiftRows and MixColumns.
+        let input: Block = [
+            0x19, 0x3d, 0xe3, 0xbe, 0xa0, 0xf4, 0xe2, 0x2b, 0x9a, 0xc6, 0x8d, 0x2a, 0xe9, 0xf8, 0x48, 0x08,
+        ];
+        let s = load(&input, &[0; NB]);
+        let output = store(&enc_round(&s, &[0; NB]));
+
+        let mut state = state::state_from_bytes(&input);
+        enc::sub_bytes(&mut state);
+        enc::shift_rows(&mut state);
+        enc::mix_columns(&mut state);
+
+        assert_eq!(state::state_to_bytes(state), output);
+    }
+}
@@ -0,0 +1,140 @@
+// Accounting of messages and blocks encrypted with a key.
+use std::sync::{Mutex, PoisonError};
+
+use crate::{Block, BlockCipher, CipherError, Mode};
+
+/// Number of messages and blocks encrypted with a key, blocks are counted per mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeyUsage {
+    pub messages: u64,
+    pub ecb_blocks: u64,
+    pub cbc_blocks: u64,
+    pub custom_blocks: u64,
+}
+
+/// Maximum usage of a key, exceeding any of the limits fails with [`CipherError::KeyExhausted`].
+///
+/// Default limits allow 2^48 blocks (4 PiB) per mode, after which the probability of
+/// two equal ciphertext blocks (2^-32) stops being negligible for a 128-bit block cipher.
+/// Number of messages is not limited by default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UsageLimits {
+    pub messages: u64,
+    pub ecb_blocks: u64,
+    pub cbc_blocks: u64,
+    pub custom_blocks: u64,
+}
+
+impl UsageLimits {
+    pub const UNLIMITED: Self = Self {
+        messages: u64::MAX,
+        ecb_blocks: u64::MAX,
+        cbc_blocks: u64::MAX,
+        custom_blocks: u64::MAX,
+    };
+}
+
+impl Default for UsageLimits {
+    fn default() -> Self {
+        Self {
+            messages: u64::MAX,
+            ecb_blocks: 1 << 48,
+            cbc_blocks: 1 << 48,
+            custom_blocks: 1 << 48,
+        }
+    }
+}
+
+type Report = Box<dyn Fn(&KeyUsage) + Send + Sync>;
+
+/// Keyed block cipher counting messages and blocks encrypted by [`Cipher`](crate::Cipher)s
+/// using it, e.g. `MeteredCipher::new(Aes256::new(&key), UsageLimits::default())`.
+/// A message is counted by every encrypting [`Cipher::init`](crate::Cipher::init) (also on resume)
+/// and [`Cipher::reset`](crate::Cipher::reset). Decryption and blocks encrypted directly
+/// with [`BlockCipher`] methods are not counted. Update or finalization exceeding a limit
+/// fails before taking any input, so the cipher stays usable for smaller updates. Requires `std`.
+pub struct MeteredCipher<C> {
+    inner: C,
+    limits: UsageLimits,
+    usage: Mutex<KeyUsage>,
+    report: Option<Report>,
+}
+
+impl<C: BlockCipher> MeteredCipher<C> {
+    pub fn new(inner: C, limits: UsageLimits) -> Self {
+        Self {
+            inner,
+            limits,
+            usage: Mutex::new(KeyUsage::default()),
+            report: None,
+        }
+    }
+
+    /// Continues counting from `usage`, e.g. restored after a restart.
+    pub fn with_usage(mut self, usage: KeyUsage) -> Self {
+        self.usage = Mutex::new(usage);
+        self
+    }
+
+    /// Calls `report` with the updated usage every time it grows, e.g. to rotate
+    /// the key before it's exhausted. It must not use this cipher.
+    pub fn on_usage(mut self, report: impl Fn(&KeyUsage) + Send + Sync + 'static) -> Self {
+        self.report = Some(Box::new(report));
+        self
+    }
+
+    pub fn usage(&self) -> KeyUsage {
+        *self.usage.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn limits(&self) -> UsageLimits {
+        self.limits
+    }
+
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
+
+impl<C: BlockCipher> BlockCipher for MeteredCipher<C> {
+    fn encrypt_block(&self, block: &Block) -> Block {
+        self.inner.encrypt_block(block)
+    }
+
+    fn decrypt_block(&self, block: &Block) -> Block {
+        self.inner.decrypt_block(block)
+    }
+
+    fn encrypt_blocks(&self, blocks: &mut [Block]) {
+        self.inner.encrypt_blocks(blocks);
+    }
+
+    fn decrypt_blocks(&self, blocks: &mut [Block]) {
+        self.inner.decrypt_blocks(blocks);
+    }
+
+    fn consume(&self, mode: &Mode, messages: u64, blocks: u64) -> Result<(), CipherError> {
+        if messages == 0 && blocks == 0 {
+            return Ok(());
+        }
+        let mut usage = self.usage.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut next = *usage;
+        let (used, limit) = match mode {
+            Mode::Ecb => (&mut next.ecb_blocks, self.limits.ecb_blocks),
+            Mode::Cbc(_) => (&mut next.cbc_blocks, self.limits.cbc_blocks),
+            Mode::Custom(_) => (&mut next.custom_blocks, self.limits.custom_blocks),
+        };
+        *used = used.saturating_add(blocks);
+        next.messages = next.messages.saturating_add(messages);
+        if *used > limit || next.messages > self.limits.messages {
+            return Err(CipherError::KeyExhausted);
+        }
+        *usage = next;
+        drop(usage);
+
+        if let Some(report) = &self.report {
+            report(&next);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+// Wiping of secret data. Writes are volatile, so the compiler can't remove them
+// as dead stores to memory which is about to be freed or go out of scope.
+use core::mem;
+use core::ptr;
+use core::sync::atomic::{compiler_fence, Ordering};
+
+/// Types for which all zero bytes is a valid value.
+///
+/// # Safety
+///
+/// Implementors must be plain data without references, padding or invalid bit patterns.
+pub(crate) unsafe trait Zeroable: Copy {}
+
+unsafe impl Zeroable for u8 {}
+unsafe impl Zeroable for u32 {}
+unsafe impl Zeroable for u64 {}
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+unsafe impl Zeroable for core::arch::x86_64::__m128i {}
+unsafe impl<T: Zeroable, const N: usize> Zeroable for [T; N] {}
+
+/// Overwrites `value` with zeroes.
+pub(crate) fn zeroize<T: Zeroable>(value: &mut T) {
+    // SAFETY: `value` is a valid, aligned reference and all zero bytes is a valid `T`.
+    unsafe { ptr::write_volatile(value, mem::zeroed()) };
+    compiler_fence(Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zeroize_clears_value() {
+        let mut words = [0xdeadbeefu32; 60];
+        zeroize(&mut words);
+        assert_eq!([0; 60], words);
+
+        let mut blocks = [[0xffu8; 16]; 4];
+        zeroize(&mut blocks[1]);
+        assert_eq!([[0xff; 16], [0; 16], [0xff; 16], [0xff; 16]], blocks);
+    }
+}
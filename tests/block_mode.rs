@@ -0,0 +1,147 @@
+use aes::{Aes128, Block, BlockCipher, BlockMode, Cipher, CipherError, Mode, Operation, Padding};
+
+const KEY: &[u8; 16] = &[
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+];
+const IV: Block = [0x24; 16];
+
+/// CBC implemented outside of the crate, has to match the built-in one.
+struct DownstreamCbc {
+    iv: Block,
+}
+
+impl BlockMode for DownstreamCbc {
+    fn encrypt(
+        &mut self,
+        blocks: &mut [Block],
+        cipher: &dyn BlockCipher,
+    ) -> Result<(), CipherError> {
+        for block in blocks {
+            for (b, c) in block.iter_mut().zip(self.iv) {
+                *b ^= c;
+            }
+            *block = cipher.encrypt_block(block);
+            self.iv = *block;
+        }
+        Ok(())
+    }
+
+    fn decrypt(
+        &mut self,
+        blocks: &mut [Block],
+        cipher: &dyn BlockCipher,
+    ) -> Result<(), CipherError> {
+        for block in blocks {
+            let ciphertext = *block;
+            *block = cipher.decrypt_block(block);
+            for (b, c) in block.iter_mut().zip(self.iv) {
+                *b ^= c;
+            }
+            self.iv = ciphertext;
+        }
+        Ok(())
+    }
+}
+
+/// Output feedback, keystream from repeated encryption of the IV.
+struct Ofb {
+    keystream: Block,
+}
+
+impl BlockMode for Ofb {
+    fn encrypt(
+        &mut self,
+        blocks: &mut [Block],
+        cipher: &dyn BlockCipher,
+    ) -> Result<(), CipherError> {
+        for block in blocks {
+            self.keystream = cipher.encrypt_block(&self.keystream);
+            for (b, k) in block.iter_mut().zip(self.keystream) {
+                *b ^= k;
+            }
+        }
+        Ok(())
+    }
+
+    fn decrypt(
+        &mut self,
+        blocks: &mut [Block],
+        cipher: &dyn BlockCipher,
+    ) -> Result<(), CipherError> {
+        self.encrypt(blocks, cipher)
+    }
+}
+
+/// Mode refusing to process more than `limit` blocks.
+struct Limited {
+    limit: usize,
+}
+
+impl BlockMode for Limited {
+    fn encrypt(&mut self, blocks: &mut [Block], _: &dyn BlockCipher) -> Result<(), CipherError> {
+        if blocks.len() > self.limit {
+            return Err(CipherError::TruncatedInput);
+        }
+        self.limit -= blocks.len();
+        Ok(())
+    }
+
+    fn decrypt(
+        &mut self,
+        blocks: &mut [Block],
+        cipher: &dyn BlockCipher,
+    ) -> Result<(), CipherError> {
+        self.encrypt(blocks, cipher)
+    }
+}
+
+fn process(operation: Operation, mode: Mode, input: &[u8], chunk_len: usize) -> Vec<u8> {
+    let aes = Aes128::new(KEY);
+    let mut cipher = Cipher::init(operation, &aes, mode, Padding::PKCS7).unwrap();
+    let mut output = Vec::new();
+    for chunk in input.chunks(chunk_len) {
+        output.extend(cipher.update(chunk).unwrap());
+    }
+    output.extend(cipher.finalize().unwrap());
+    output
+}
+
+#[test]
+fn custom_mode_matches_built_in() {
+    let plaintext: Vec<u8> = (0..100).collect();
+    let expected = process(Operation::Encrypt, Mode::Cbc(IV), &plaintext, 100);
+    for chunk_len in [1, 7, 16, 33] {
+        let mode = Mode::Custom(Box::new(DownstreamCbc { iv: IV }));
+        let ciphertext = process(Operation::Encrypt, mode, &plaintext, chunk_len);
+        assert_eq!(expected, ciphertext);
+
+        let mode = Mode::Custom(Box::new(DownstreamCbc { iv: IV }));
+        assert_eq!(
+            plaintext,
+            process(Operation::Decrypt, mode, &ciphertext, chunk_len)
+        );
+    }
+}
+
+#[test]
+fn custom_mode_round_trip() {
+    let plaintext = "Output feedback plugged into the Cipher buffering.".as_bytes();
+    let mode = Mode::Custom(Box::new(Ofb { keystream: IV }));
+    let ciphertext = process(Operation::Encrypt, mode, plaintext, 5);
+    assert_eq!(64, ciphertext.len());
+
+    let mode = Mode::Custom(Box::new(Ofb { keystream: IV }));
+    assert_eq!(
+        plaintext,
+        process(Operation::Decrypt, mode, &ciphertext, 11)
+    );
+}
+
+#[test]
+fn custom_mode_error_is_propagated() {
+    let aes = Aes128::new(KEY);
+    let mode = Mode::Custom(Box::new(Limited { limit: 2 }));
+    let mut cipher = Cipher::init(Operation::Encrypt, &aes, mode, Padding::PKCS7).unwrap();
+    assert_eq!(32, cipher.update(&[0; 40]).unwrap().len());
+    assert_eq!(Err(CipherError::TruncatedInput), cipher.finalize());
+}
@@ -1,4 +1,4 @@
-use aes::{Cipher, Mode, Operation, Padding};
+use aes::{Aes128, Cipher, Mode, Operation, Padding};
 use std::process::{Command, Stdio};
 
 #[test]
@@ -9,6 +9,7 @@ fn cbc_integration_test() {
         0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
         0x0f,
     ];
+    let aes = Aes128::new(key);
 
     let iv = &[
         0x0f, 0x0e, 0x0d, 0x0c, 0x0b, 0x0a, 0x09, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01,
@@ -23,18 +24,18 @@ fn cbc_integration_test() {
 
     let ciphertext = {
         let mut cipher =
-            Cipher::init(Operation::Encrypt, key, Mode::Cbc(*iv), Padding::PKCS7).unwrap();
+            Cipher::init(Operation::Encrypt, &aes, Mode::Cbc(*iv), Padding::PKCS7).unwrap();
         let mut ciphertext = Vec::with_capacity(plaintext.len() + 16);
-        ciphertext.extend(cipher.update(plaintext));
+        ciphertext.extend(cipher.update(plaintext).unwrap());
         ciphertext.extend(cipher.finalize().unwrap());
         ciphertext
     };
 
     let recovered = {
         let mut cipher =
-            Cipher::init(Operation::Decrypt, key, Mode::Cbc(*iv), Padding::PKCS7).unwrap();
+            Cipher::init(Operation::Decrypt, &aes, Mode::Cbc(*iv), Padding::PKCS7).unwrap();
         let mut recovered = Vec::with_capacity(plaintext.len() + 16);
-        recovered.extend(cipher.update(&ciphertext));
+        recovered.extend(cipher.update(&ciphertext).unwrap());
         recovered.extend(cipher.finalize().unwrap());
         recovered
     };
@@ -66,16 +67,17 @@ print(output.hex())
 "
     );
 
-    let echo_py_script = Command::new("echo")
+    let mut echo_py_script = Command::new("echo")
         .arg(py_script)
         .stdout(Stdio::piped())
         .spawn()
         .unwrap();
 
     let exec_py_script = Command::new("python3")
-        .stdin(Stdio::from(echo_py_script.stdout.unwrap()))
+        .stdin(Stdio::from(echo_py_script.stdout.take().unwrap()))
         .output()
         .unwrap();
+    echo_py_script.wait().unwrap();
 
     let output_py_script = String::from_utf8(exec_py_script.stdout)
         .unwrap()
@@ -104,16 +106,17 @@ print(output.hex())
 "
     );
 
-    let echo_py_script = Command::new("echo")
+    let mut echo_py_script = Command::new("echo")
         .arg(py_script)
         .stdout(Stdio::piped())
         .spawn()
         .unwrap();
 
     let exec_py_script = Command::new("python3")
-        .stdin(Stdio::from(echo_py_script.stdout.unwrap()))
+        .stdin(Stdio::from(echo_py_script.stdout.take().unwrap()))
         .output()
         .unwrap();
+    echo_py_script.wait().unwrap();
 
     let output_py_script = String::from_utf8(exec_py_script.stdout)
         .unwrap()
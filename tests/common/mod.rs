@@ -0,0 +1,35 @@
+//! Fixtures shared by the integration tests, each test uses only some of them.
+#![allow(dead_code)]
+
+use aes::{CipherError, EntropySource, Padder};
+
+/// ANSI X9.23: zeroes followed by the padding length in the last byte.
+pub struct AnsiX923;
+
+impl Padder for AnsiX923 {
+    fn pad(&self, input: &[u8], output: &mut [u8]) -> Result<usize, CipherError> {
+        output[..input.len()].copy_from_slice(input);
+        output[input.len()..15].fill(0);
+        output[15] = (16 - input.len()) as u8;
+        Ok(16)
+    }
+
+    fn unpad<'a>(&self, input: &'a [u8]) -> Result<&'a [u8], CipherError> {
+        let pad_len = input[input.len() - 1] as usize;
+        if pad_len == 0 || pad_len > input.len() {
+            return Err(CipherError::InvalidPadding);
+        }
+        Ok(&input[..input.len() - pad_len])
+    }
+}
+
+/// Deterministic source, every IV is a block of the next counter value.
+pub struct Counter(pub u8);
+
+impl EntropySource for Counter {
+    fn fill(&mut self, bytes: &mut [u8]) -> Result<(), CipherError> {
+        self.0 += 1;
+        bytes.fill(self.0);
+        Ok(())
+    }
+}
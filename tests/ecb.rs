@@ -1,4 +1,4 @@
-use aes::{Cipher, Mode, Operation, Padding};
+use aes::{Aes128, Cipher, Mode, Operation, Padding};
 use std::process::{Command, Stdio};
 
 #[test]
@@ -9,6 +9,7 @@ fn ecb_integration_test() {
         0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
         0x0f,
     ];
+    let aes = Aes128::new(key);
 
     let plaintext = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. \
         Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat. \
@@ -17,17 +18,17 @@ fn ecb_integration_test() {
     let plaintext = plaintext.as_bytes();
 
     let ciphertext = {
-        let mut cipher = Cipher::init(Operation::Encrypt, key, Mode::Ecb, Padding::PKCS7).unwrap();
+        let mut cipher = Cipher::init(Operation::Encrypt, &aes, Mode::Ecb, Padding::PKCS7).unwrap();
         let mut ciphertext = Vec::with_capacity(plaintext.len() + 16);
-        ciphertext.extend(cipher.update(plaintext));
+        ciphertext.extend(cipher.update(plaintext).unwrap());
         ciphertext.extend(cipher.finalize().unwrap());
         ciphertext
     };
 
     let recovered = {
-        let mut cipher = Cipher::init(Operation::Decrypt, key, Mode::Ecb, Padding::PKCS7).unwrap();
+        let mut cipher = Cipher::init(Operation::Decrypt, &aes, Mode::Ecb, Padding::PKCS7).unwrap();
         let mut recovered = Vec::with_capacity(plaintext.len() + 16);
-        recovered.extend(cipher.update(&ciphertext));
+        recovered.extend(cipher.update(&ciphertext).unwrap());
         recovered.extend(cipher.finalize().unwrap());
         recovered
     };
@@ -57,16 +58,17 @@ print(output.hex())
 "
     );
 
-    let echo_py_script = Command::new("echo")
+    let mut echo_py_script = Command::new("echo")
         .arg(py_script)
         .stdout(Stdio::piped())
         .spawn()
         .unwrap();
 
     let exec_py_script = Command::new("python3")
-        .stdin(Stdio::from(echo_py_script.stdout.unwrap()))
+        .stdin(Stdio::from(echo_py_script.stdout.take().unwrap()))
         .output()
         .unwrap();
+    echo_py_script.wait().unwrap();
 
     let output_py_script = String::from_utf8(exec_py_script.stdout)
         .unwrap()
@@ -93,16 +95,17 @@ output = padder.update(padded_output) + padder.finalize()
 print(output.hex())
 "
     );
-    let echo_py_script = Command::new("echo")
+    let mut echo_py_script = Command::new("echo")
         .arg(py_script)
         .stdout(Stdio::piped())
         .spawn()
         .unwrap();
 
     let exec_py_script = Command::new("python3")
-        .stdin(Stdio::from(echo_py_script.stdout.unwrap()))
+        .stdin(Stdio::from(echo_py_script.stdout.take().unwrap()))
         .output()
         .unwrap();
+    echo_py_script.wait().unwrap();
 
     let output_py_script = String::from_utf8(exec_py_script.stdout)
         .unwrap()
@@ -0,0 +1,61 @@
+use aes::{Armor, Base64, CipherError, Hex, Key, Operation, Transformation};
+
+const KEY_HEX: &str = "000102030405060708090A0B0C0D0E0F101112131415161718191A1B1C1D1E1F";
+
+#[test]
+fn armored_message_round_trip() {
+    let key = Key::new(&Hex::decode(KEY_HEX).unwrap()).unwrap();
+    assert_eq!(KEY_HEX, Hex::Upper.encode(key.as_bytes()));
+    let transformation = Transformation::parse("AES_256/CBC/PKCS5Padding").unwrap();
+    let iv = Hex::decode("24242424242424242424242424242424").unwrap();
+    let plaintext =
+        "Armored messages carry everything needed for decryption but the key.".repeat(3);
+
+    let mut cipher = transformation
+        .cipher(Operation::Encrypt, &key, &iv)
+        .unwrap();
+    let mut ciphertext = cipher.update(plaintext.as_bytes()).unwrap();
+    ciphertext.extend(cipher.finalize().unwrap());
+    let text = Armor::new(transformation, &iv, &ciphertext)
+        .unwrap()
+        .to_string();
+    assert!(text.lines().all(|line| line.len() <= 64));
+
+    let armor: Armor = text.parse().unwrap();
+    assert_eq!(ciphertext, armor.ciphertext());
+    let mut cipher = armor.decryptor(&key).unwrap();
+    let mut recovered = cipher.update(armor.ciphertext()).unwrap();
+    recovered.extend(cipher.finalize().unwrap());
+    assert_eq!(plaintext.as_bytes(), recovered);
+
+    let wrong_size = Key::new(&[0; 16]).unwrap();
+    assert_eq!(
+        Some(CipherError::KeySizeMismatch {
+            expected: 256,
+            actual: 128
+        }),
+        armor.decryptor(&wrong_size).err()
+    );
+}
+
+#[test]
+fn base64_variants_round_trip() {
+    let variants = [
+        Base64::Standard,
+        Base64::StandardUnpadded,
+        Base64::UrlSafe,
+        Base64::UrlSafeUnpadded,
+    ];
+    for len in 0..70 {
+        let bytes: Vec<u8> = (0..len).map(|i| (i * 151 + 3) as u8).collect();
+        for variant in variants {
+            let text = variant.encode(&bytes);
+            assert_eq!(
+                Ok(bytes.clone()),
+                variant.decode(&text),
+                "{variant:?} {len}"
+            );
+        }
+        assert_eq!(Ok(bytes.clone()), Hex::decode(&Hex::Lower.encode(&bytes)));
+    }
+}
@@ -0,0 +1,165 @@
+use aes::{Aes128, Cipher, CipherError, Mode, Operation, Padding};
+
+const KEY: &[u8; 16] = &[
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+];
+const IV: [u8; 16] = [0x24; 16];
+
+fn modes() -> [fn() -> Mode; 2] {
+    [|| Mode::Ecb, || Mode::Cbc(IV)]
+}
+
+fn plaintext(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 31 + 7) as u8).collect()
+}
+
+fn one_shot(operation: Operation, mode: Mode, input: &[u8]) -> Vec<u8> {
+    let aes = Aes128::new(KEY);
+    let mut cipher = Cipher::init(operation, &aes, mode, Padding::PKCS7).unwrap();
+    let mut output = cipher.update(input).unwrap();
+    output.extend(cipher.finalize().unwrap());
+    output
+}
+
+fn chunked_into(operation: Operation, mode: Mode, input: &[u8], chunk_len: usize) -> Vec<u8> {
+    let aes = Aes128::new(KEY);
+    let mut cipher = Cipher::init(operation, &aes, mode, Padding::PKCS7).unwrap();
+    let mut output = vec![0u8; input.len() + 16];
+    let mut written = 0;
+    for chunk in input.chunks(chunk_len) {
+        let expected = cipher.update_len(chunk.len());
+        let len = cipher.update_into(chunk, &mut output[written..]).unwrap();
+        assert_eq!(expected, len);
+        written += len;
+    }
+    written += cipher.finalize_into(&mut output[written..]).unwrap();
+    output.truncate(written);
+    output
+}
+
+fn chunked_in_place(operation: Operation, mode: Mode, input: &[u8], chunk_len: usize) -> Vec<u8> {
+    let aes = Aes128::new(KEY);
+    let mut cipher = Cipher::init(operation, &aes, mode, Padding::PKCS7).unwrap();
+    let mut output = Vec::new();
+    for chunk in input.chunks(chunk_len) {
+        let mut data = chunk.to_vec();
+        let len = cipher.update_in_place(&mut data).unwrap();
+        output.extend(&data[..len]);
+    }
+    let mut last = [0u8; 32];
+    let len = cipher.finalize_into(&mut last).unwrap();
+    output.extend(&last[..len]);
+    output
+}
+
+#[test]
+fn update_into_matches_update() {
+    for mode in modes() {
+        for len in [0, 1, 15, 16, 17, 100, 256] {
+            let plaintext = plaintext(len);
+            let ciphertext = one_shot(Operation::Encrypt, mode(), &plaintext);
+            for chunk_len in [1, 3, 16, 17, 64] {
+                assert_eq!(
+                    ciphertext,
+                    chunked_into(Operation::Encrypt, mode(), &plaintext, chunk_len)
+                );
+                assert_eq!(
+                    plaintext,
+                    chunked_into(Operation::Decrypt, mode(), &ciphertext, chunk_len)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn update_in_place_matches_update() {
+    for mode in modes() {
+        for len in [0, 16, 48, 255, 256] {
+            let plaintext = plaintext(len);
+            let ciphertext = one_shot(Operation::Encrypt, mode(), &plaintext);
+            for chunk_len in [16, 32, 80] {
+                let aligned = plaintext.len() - plaintext.len() % 16;
+                let mut encrypted =
+                    chunked_in_place(Operation::Encrypt, mode(), &plaintext[..aligned], chunk_len);
+                // Only the padding block is produced by the finalization.
+                encrypted.truncate(aligned);
+                assert_eq!(ciphertext[..aligned], encrypted);
+
+                assert_eq!(
+                    plaintext,
+                    chunked_in_place(Operation::Decrypt, mode(), &ciphertext, chunk_len)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn update_into_fails_on_too_small_output() {
+    let aes = Aes128::new(KEY);
+    let mut cipher = Cipher::init(Operation::Encrypt, &aes, Mode::Ecb, Padding::PKCS7).unwrap();
+    assert_eq!(
+        Err(CipherError::OutputTooSmall {
+            required: 32,
+            len: 31
+        }),
+        cipher.update_into(&[0; 32], &mut [0; 31])
+    );
+    // Failed update does not consume the input.
+    assert_eq!(32, cipher.update_len(32));
+}
+
+#[test]
+fn update_len_saturates() {
+    let aes = Aes128::new(KEY);
+    for operation in [Operation::Encrypt, Operation::Decrypt] {
+        let mut cipher = Cipher::init(operation, &aes, Mode::Ecb, Padding::PKCS7).unwrap();
+        cipher.update(&[0; 5]).unwrap();
+        assert_eq!(usize::MAX - 15, cipher.update_len(usize::MAX));
+    }
+}
+
+#[test]
+fn update_in_place_fails_on_unaligned_data() {
+    let aes = Aes128::new(KEY);
+    let mut cipher = Cipher::init(Operation::Encrypt, &aes, Mode::Ecb, Padding::PKCS7).unwrap();
+    assert_eq!(
+        Err(CipherError::UnalignedInput { len: 17 }),
+        cipher.update_in_place(&mut [0; 17])
+    );
+}
+
+#[test]
+fn finalize_into_fails_on_too_small_output() {
+    let aes = Aes128::new(KEY);
+    let mut cipher = Cipher::init(Operation::Encrypt, &aes, Mode::Ecb, Padding::PKCS7).unwrap();
+    assert_eq!(16, cipher.finalize_len());
+    assert_eq!(
+        Err(CipherError::OutputTooSmall {
+            required: 16,
+            len: 15
+        }),
+        cipher.finalize_into(&mut [0; 15])
+    );
+}
+
+#[test]
+fn decryption_fails_on_truncated_ciphertext() {
+    let aes = Aes128::new(KEY);
+    let mut cipher = Cipher::init(Operation::Decrypt, &aes, Mode::Ecb, Padding::PKCS7).unwrap();
+    assert_eq!(0, cipher.update_into(&[0; 15], &mut []).unwrap());
+    assert_eq!(
+        Err(CipherError::TruncatedInput),
+        cipher.finalize_into(&mut [0; 16])
+    );
+}
+
+#[test]
+fn cbc_requires_one_block_iv() {
+    assert!(matches!(Mode::cbc(&IV), Ok(Mode::Cbc(iv)) if iv == IV));
+    assert!(matches!(
+        Mode::cbc(&IV[..15]),
+        Err(CipherError::InvalidIvLength { len: 15 })
+    ));
+}
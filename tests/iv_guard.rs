@@ -0,0 +1,150 @@
+use aes::{
+    Aes128, Aes256, Cipher, CipherError, FileIvStore, IvGuard, MeteredCipher, Mode, Operation,
+    Padding, UsageLimits,
+};
+use std::{fs, path::PathBuf};
+
+const IV: [u8; 16] = [0x24; 16];
+
+fn store_path(name: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_file(&path);
+    path
+}
+
+fn encryptor<'a>(
+    key: &'a Aes128,
+    mode: Mode,
+    guard: &mut IvGuard,
+) -> Result<Cipher<'a>, CipherError> {
+    Cipher::init_guarded(Operation::Encrypt, key, mode, Padding::PKCS7, guard)
+}
+
+#[test]
+fn repeated_iv_is_refused() {
+    let first = Aes128::new(&[0x42; 16]);
+    let second = Aes128::new(&[0x17; 16]);
+    let mut guard = IvGuard::in_memory();
+
+    assert!(encryptor(&first, Mode::Cbc(IV), &mut guard).is_ok());
+    assert_eq!(
+        Some(CipherError::IvReused),
+        encryptor(&first, Mode::Cbc(IV), &mut guard).err()
+    );
+    // Same IV with another key, decryption and ECB are not restricted.
+    assert!(encryptor(&second, Mode::Cbc(IV), &mut guard).is_ok());
+    assert!(encryptor(&first, Mode::Cbc([0x25; 16]), &mut guard).is_ok());
+    for _ in 0..2 {
+        assert!(Cipher::init_guarded(
+            Operation::Decrypt,
+            &first,
+            Mode::Cbc(IV),
+            Padding::PKCS7,
+            &mut guard
+        )
+        .is_ok());
+        assert!(encryptor(&first, Mode::Ecb, &mut guard).is_ok());
+    }
+
+    // Keys of different sizes are told apart as well.
+    let aes256 = Aes256::new(&[0x42; 32]);
+    assert!(Cipher::init_guarded(
+        Operation::Encrypt,
+        &aes256,
+        Mode::Cbc(IV),
+        Padding::PKCS7,
+        &mut guard
+    )
+    .is_ok());
+}
+
+#[test]
+fn reset_is_guarded() {
+    let key = Aes128::new(&[0x42; 16]);
+    let mut guard = IvGuard::in_memory();
+    let mut cipher = encryptor(&key, Mode::Cbc(IV), &mut guard).unwrap();
+    cipher.finalize_into(&mut [0; 16]).unwrap();
+
+    assert_eq!(
+        Err(CipherError::IvReused),
+        cipher.reset_guarded(&IV, &mut guard)
+    );
+    assert_eq!(
+        Err(CipherError::InvalidIvLength { len: 8 }),
+        cipher.reset_guarded(&[0; 8], &mut guard)
+    );
+    assert_eq!(Ok(()), cipher.reset_guarded(&[0x25; 16], &mut guard));
+    assert_eq!(
+        Err(CipherError::IvReused),
+        cipher.reset_guarded(&[0x25; 16], &mut guard)
+    );
+}
+
+#[test]
+fn failed_init_does_not_record_iv() {
+    let limits = UsageLimits {
+        messages: 0,
+        ..UsageLimits::default()
+    };
+    let exhausted = MeteredCipher::new(Aes128::new(&[0x42; 16]), limits);
+    let mut guard = IvGuard::in_memory();
+    assert_eq!(
+        Some(CipherError::KeyExhausted),
+        Cipher::init_guarded(
+            Operation::Encrypt,
+            &exhausted,
+            Mode::Cbc(IV),
+            Padding::PKCS7,
+            &mut guard
+        )
+        .err()
+    );
+
+    let key = Aes128::new(&[0x42; 16]);
+    assert!(encryptor(&key, Mode::Cbc(IV), &mut guard).is_ok());
+}
+
+#[test]
+fn file_store_survives_reopen() {
+    let path = store_path("iv_guard_reopen");
+    let key = Aes128::new(&[0x42; 16]);
+
+    let mut guard = IvGuard::new(FileIvStore::open(&path).unwrap());
+    for i in 0..10u8 {
+        assert!(encryptor(&key, Mode::Cbc([i; 16]), &mut guard).is_ok());
+    }
+    drop(guard);
+    assert_eq!(320, fs::metadata(&path).unwrap().len());
+
+    let store = FileIvStore::open(&path).unwrap();
+    assert_eq!(10, store.len());
+    let mut guard = IvGuard::new(store);
+    assert_eq!(
+        Some(CipherError::IvReused),
+        encryptor(&key, Mode::Cbc([3; 16]), &mut guard).err()
+    );
+    assert!(encryptor(&key, Mode::Cbc([10; 16]), &mut guard).is_ok());
+
+    // Key itself is not stored.
+    let contents = fs::read(&path).unwrap();
+    assert!(!contents.windows(16).any(|window| window == [0x42; 16]));
+}
+
+#[test]
+fn torn_record_is_dropped() {
+    let path = store_path("iv_guard_torn");
+    let key = Aes128::new(&[0x42; 16]);
+    let mut guard = IvGuard::new(FileIvStore::open(&path).unwrap());
+    guard.record(&key, &IV).unwrap();
+    drop(guard);
+
+    let mut contents = fs::read(&path).unwrap();
+    contents.extend_from_slice(&[0xff; 20]);
+    fs::write(&path, &contents).unwrap();
+
+    let store = FileIvStore::open(&path).unwrap();
+    assert_eq!(1, store.len());
+    assert_eq!(32, fs::metadata(&path).unwrap().len());
+    let mut guard = IvGuard::new(store);
+    assert_eq!(Err(CipherError::IvReused), guard.record(&key, &IV));
+}
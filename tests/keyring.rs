@@ -0,0 +1,131 @@
+mod common;
+
+use aes::{CipherError, Key, KeyStatus, Keyring};
+use common::Counter;
+
+fn key(byte: u8) -> Key {
+    Key::new(&[byte; 32]).unwrap()
+}
+
+#[test]
+fn rotated_keys_decrypt_old_messages() {
+    let mut keyring = Keyring::new();
+    let mut entropy = Counter(0);
+    keyring.add(1, key(0x11)).unwrap();
+    keyring.set_primary(1).unwrap();
+    let january = keyring
+        .encrypt_with(b"january record", &mut entropy)
+        .unwrap();
+    assert_eq!(Ok(1), Keyring::key_id(&january));
+    assert_eq!(&[1, 0, 0, 0, 1], &january[..5]);
+
+    keyring.add(2, key(0x22)).unwrap();
+    keyring.set_primary(2).unwrap();
+    keyring.retire(1).unwrap();
+    let february = keyring
+        .encrypt_with(b"february record", &mut entropy)
+        .unwrap();
+    assert_eq!(Ok(2), Keyring::key_id(&february));
+
+    assert_eq!(b"january record", &keyring.decrypt(&january).unwrap()[..]);
+    assert_eq!(b"february record", &keyring.decrypt(&february).unwrap()[..]);
+    assert_eq!(vec![1, 2], keyring.ids().collect::<Vec<_>>());
+    assert_eq!(Some(KeyStatus::Retired), keyring.status(1));
+    assert_eq!(Some(KeyStatus::Primary), keyring.status(2));
+
+    keyring.destroy(1).unwrap();
+    assert_eq!(None, keyring.status(1));
+    assert_eq!(
+        Err(CipherError::UnknownKey { id: 1 }),
+        keyring.decrypt(&january)
+    );
+    assert_eq!(b"february record", &keyring.decrypt(&february).unwrap()[..]);
+}
+
+#[test]
+fn key_states_are_checked() {
+    let mut keyring = Keyring::new();
+    assert_eq!(
+        Err(CipherError::NoPrimaryKey),
+        keyring.encrypt_with(b"message", &mut Counter(0))
+    );
+    keyring.add(1, key(0x11)).unwrap();
+    assert_eq!(
+        Err(CipherError::DuplicateKey { id: 1 }),
+        keyring.add(1, key(0x12))
+    );
+    assert_eq!(
+        Err(CipherError::UnknownKey { id: 7 }),
+        keyring.set_primary(7)
+    );
+    assert_eq!(Err(CipherError::UnknownKey { id: 7 }), keyring.retire(7));
+    assert_eq!(Err(CipherError::UnknownKey { id: 7 }), keyring.destroy(7));
+
+    keyring.add(2, key(0x22)).unwrap();
+    keyring.set_primary(1).unwrap();
+    keyring.set_primary(2).unwrap();
+    assert_eq!(Some(KeyStatus::Active), keyring.status(1));
+    assert_eq!(Some(2), keyring.primary());
+
+    // Retiring the primary key stops encryption until a new one is chosen.
+    keyring.retire(2).unwrap();
+    assert_eq!(None, keyring.primary());
+    assert_eq!(
+        Err(CipherError::NoPrimaryKey),
+        keyring.encrypt_with(b"message", &mut Counter(0))
+    );
+    assert_eq!(
+        Err(CipherError::KeyRetired { id: 2 }),
+        keyring.set_primary(2)
+    );
+}
+
+#[test]
+fn malformed_messages_are_rejected() {
+    let mut keyring = Keyring::new();
+    keyring.add(1, key(0x11)).unwrap();
+    keyring.set_primary(1).unwrap();
+    let message = keyring.encrypt_with(b"message", &mut Counter(0)).unwrap();
+    assert_eq!(5 + 16 + 16, message.len());
+
+    assert_eq!(
+        Err(CipherError::TruncatedInput),
+        keyring.decrypt(&message[..20])
+    );
+    assert_eq!(
+        Err(CipherError::TruncatedInput),
+        keyring.decrypt(&message[..30])
+    );
+    let mut unknown_version = message.clone();
+    unknown_version[0] = 2;
+    assert_eq!(
+        Err(CipherError::InvalidState),
+        keyring.decrypt(&unknown_version)
+    );
+}
+
+#[test]
+fn tampered_key_id_fails() {
+    let mut keyring = Keyring::new();
+    keyring.add(1, key(0x11)).unwrap();
+    keyring.add(2, key(0x22)).unwrap();
+    keyring.set_primary(1).unwrap();
+    let message = keyring.encrypt_with(b"message", &mut Counter(0)).unwrap();
+
+    // Header isn't authenticated, the message is decrypted with key 2 and only
+    // the padding check catches it.
+    let mut tampered = message.clone();
+    tampered[4] = 2;
+    assert_eq!(Ok(2), Keyring::key_id(&tampered));
+    assert_eq!(Err(CipherError::InvalidPadding), keyring.decrypt(&tampered));
+}
+
+#[cfg(all(feature = "std", unix))]
+#[test]
+fn os_entropy_encryption() {
+    let mut keyring = Keyring::new();
+    keyring.add(3, key(0x33)).unwrap();
+    keyring.set_primary(3).unwrap();
+    let message = keyring.encrypt(b"message").unwrap();
+    assert_eq!(b"message", &keyring.decrypt(&message).unwrap()[..]);
+}
@@ -0,0 +1,61 @@
+//! Steady state encryption and decryption with the `_into` methods must not allocate.
+use aes::{Aes128, Cipher, Mode, Operation, Padding};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|count| count.get())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn into_methods_do_not_allocate() {
+    let aes = Aes128::new(&[0x42; 16]);
+    let packet = [0x5a; 100];
+    let mut ciphertext = [0u8; 128];
+    let mut plaintext = [0u8; 128];
+
+    let before = allocations();
+    for _ in 0..16 {
+        let mut cipher =
+            Cipher::init(Operation::Encrypt, &aes, Mode::Cbc([1; 16]), Padding::PKCS7).unwrap();
+        let mut len = 0;
+        for chunk in packet.chunks(7) {
+            len += cipher.update_into(chunk, &mut ciphertext[len..]).unwrap();
+        }
+        len += cipher.finalize_into(&mut ciphertext[len..]).unwrap();
+        assert_eq!(112, len);
+        let ciphertext = &ciphertext[..len];
+
+        let mut cipher =
+            Cipher::init(Operation::Decrypt, &aes, Mode::Cbc([1; 16]), Padding::PKCS7).unwrap();
+        let mut len = cipher.update_into(ciphertext, &mut plaintext).unwrap();
+        len += cipher.finalize_into(&mut plaintext[len..]).unwrap();
+        assert_eq!(packet, plaintext[..len]);
+
+        let mut cipher = Cipher::init(Operation::Encrypt, &aes, Mode::Ecb, Padding::PKCS7).unwrap();
+        let mut data = [0x33; 96];
+        assert_eq!(96, cipher.update_in_place(&mut data).unwrap());
+    }
+    assert_eq!(before, allocations());
+}
@@ -0,0 +1,87 @@
+use aes::{Aes256, Backend, Cipher, CipherError, Key, Mode, Operation, Padding};
+
+const KEY: [u8; 32] = [0x5c; 32];
+const IV: [u8; 16] = [0x24; 16];
+
+// Owned cipher doesn't borrow the key, so it can be stored next to other state.
+struct Session {
+    cipher: Cipher<'static>,
+    output: Vec<u8>,
+}
+
+impl Session {
+    fn new(operation: Operation, key: &Key) -> Self {
+        Self {
+            cipher: Cipher::with_key(operation, key, Mode::Cbc(IV), Padding::PKCS7).unwrap(),
+            output: Vec::new(),
+        }
+    }
+
+    fn feed(&mut self, input: &[u8]) {
+        let output = self.cipher.update(input).unwrap();
+        self.output.extend(output);
+    }
+
+    fn finish(self) -> Vec<u8> {
+        let mut output = self.output;
+        output.extend(self.cipher.finalize().unwrap());
+        output
+    }
+}
+
+fn borrowed(operation: Operation, input: &[u8]) -> Vec<u8> {
+    let aes = Aes256::new(&KEY);
+    let mut cipher = Cipher::init(operation, &aes, Mode::Cbc(IV), Padding::PKCS7).unwrap();
+    let mut output = cipher.update(input).unwrap();
+    output.extend(cipher.finalize().unwrap());
+    output
+}
+
+#[test]
+fn with_key_matches_borrowed_cipher() {
+    let plaintext: Vec<u8> = (0..100).collect();
+
+    let key = Key::new(&KEY).unwrap();
+    let mut session = Session::new(Operation::Encrypt, &key);
+    drop(key);
+    for chunk in plaintext.chunks(7) {
+        session.feed(chunk);
+    }
+    let ciphertext = session.finish();
+    assert_eq!(borrowed(Operation::Encrypt, &plaintext), ciphertext);
+
+    let key = Key::new(&KEY).unwrap();
+    let mut session = Session::new(Operation::Decrypt, &key);
+    session.feed(&ciphertext);
+    assert_eq!(plaintext, session.finish());
+}
+
+#[test]
+fn init_owned_matches_borrowed_cipher() {
+    let plaintext = b"owned block cipher".to_vec();
+    for backend in [Backend::Reference, Backend::TTable, Backend::Bitsliced] {
+        let key = Key::new(&KEY).unwrap().block_cipher(backend);
+        let mut cipher =
+            Cipher::init_owned(Operation::Encrypt, key, Mode::Cbc(IV), Padding::PKCS7).unwrap();
+        let mut ciphertext = cipher.update(&plaintext).unwrap();
+        ciphertext.extend(cipher.finalize().unwrap());
+        assert_eq!(borrowed(Operation::Encrypt, &plaintext), ciphertext);
+    }
+}
+
+#[test]
+fn key_length_is_validated() {
+    assert_eq!(128, Key::new(&[0; 16]).unwrap().bits());
+    assert_eq!(192, Key::new(&[0; 24]).unwrap().bits());
+    assert_eq!(256, Key::new(&[0; 32]).unwrap().bits());
+    assert_eq!(
+        CipherError::InvalidKeyLength { bits: 120 },
+        Key::new(&[0; 15]).unwrap_err()
+    );
+}
+
+#[test]
+fn key_debug_hides_bytes() {
+    let key = Key::new(&[0xab; 16]).unwrap();
+    assert_eq!("Key { bits: 128, .. }", format!("{key:?}"));
+}
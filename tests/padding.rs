@@ -0,0 +1,164 @@
+mod common;
+
+use aes::{Aes128, Cipher, CipherError, Mode, Operation, Padder, Padding};
+use common::AnsiX923;
+
+/// Broken padder which does not extend input to the block size.
+struct Truncating;
+
+impl Padder for Truncating {
+    fn pad(&self, input: &[u8], output: &mut [u8]) -> Result<usize, CipherError> {
+        output[..input.len()].copy_from_slice(input);
+        Ok(input.len())
+    }
+
+    fn unpad<'a>(&self, input: &'a [u8]) -> Result<&'a [u8], CipherError> {
+        Ok(input)
+    }
+}
+
+/// Padder extending input with a whole extra block, which couldn't be unpadded.
+struct TwoBlocks;
+
+impl Padder for TwoBlocks {
+    fn pad(&self, input: &[u8], output: &mut [u8]) -> Result<usize, CipherError> {
+        output[..input.len()].copy_from_slice(input);
+        Ok(2 * 16)
+    }
+
+    fn unpad<'a>(&self, input: &'a [u8]) -> Result<&'a [u8], CipherError> {
+        Ok(input)
+    }
+}
+
+const KEY: &[u8; 16] = &[
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+];
+
+fn encrypt(plaintext: &[u8], padding: Padding) -> Vec<u8> {
+    let aes = Aes128::new(KEY);
+    let mut cipher = Cipher::init(Operation::Encrypt, &aes, Mode::Cbc([7; 16]), padding).unwrap();
+    let mut ciphertext = cipher.update(plaintext).unwrap();
+    ciphertext.extend(cipher.finalize().unwrap());
+    ciphertext
+}
+
+fn decrypt(ciphertext: &[u8], padding: Padding) -> Vec<u8> {
+    let aes = Aes128::new(KEY);
+    let mut cipher = Cipher::init(Operation::Decrypt, &aes, Mode::Cbc([7; 16]), padding).unwrap();
+    let mut plaintext = cipher.update(ciphertext).unwrap();
+    plaintext.extend(cipher.finalize().unwrap());
+    plaintext
+}
+
+#[test]
+fn custom_padding_round_trip() {
+    let plaintext = "Custom padding schemes are driven like PKCS7.".as_bytes();
+
+    let ciphertext = encrypt(plaintext, Padding::Custom(Box::new(AnsiX923)));
+    let pkcs7_ciphertext = encrypt(plaintext, Padding::PKCS7);
+    assert_eq!(ciphertext.len(), pkcs7_ciphertext.len());
+    assert_ne!(ciphertext, pkcs7_ciphertext);
+
+    let recovered = decrypt(&ciphertext, Padding::Custom(Box::new(AnsiX923)));
+    assert_eq!(plaintext, recovered);
+}
+
+#[test]
+fn custom_padding_error_is_propagated() {
+    let aes = Aes128::new(KEY);
+    // Block of zeroes ends with 0x00 which is an invalid length for ANSI X9.23.
+    let mut cipher = Cipher::init(Operation::Encrypt, &aes, Mode::Ecb, Padding::PKCS7).unwrap();
+    let ciphertext = cipher.update(&[0; 16]).unwrap();
+
+    let mut cipher = Cipher::init(
+        Operation::Decrypt,
+        &aes,
+        Mode::Ecb,
+        Padding::Custom(Box::new(AnsiX923)),
+    )
+    .unwrap();
+    assert!(cipher.update(&ciphertext).unwrap().is_empty());
+    assert_eq!(Err(CipherError::InvalidPadding), cipher.finalize());
+}
+
+#[test]
+fn malformed_pkcs7_padding_is_rejected() {
+    let plaintext = b"Wrong key must not give back garbage plaintext.";
+    let ciphertext = encrypt(plaintext, Padding::PKCS7);
+
+    // Wrong key or tampered block decrypt to random bytes, with these inputs they are
+    // not valid padding. Padding is not a MAC, about 1 in 256 random blocks ends with 0x01.
+    let wrong_key = Aes128::new(&[0x43; 16]);
+    let mut cipher = Cipher::init(
+        Operation::Decrypt,
+        &wrong_key,
+        Mode::Cbc([7; 16]),
+        Padding::PKCS7,
+    )
+    .unwrap();
+    cipher.update(&ciphertext).unwrap();
+    assert_eq!(Err(CipherError::InvalidPadding), cipher.finalize());
+
+    let mut tampered = ciphertext.clone();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 0x01;
+    let aes = Aes128::new(KEY);
+    let mut cipher =
+        Cipher::init(Operation::Decrypt, &aes, Mode::Cbc([7; 16]), Padding::PKCS7).unwrap();
+    cipher.update(&tampered).unwrap();
+    assert_eq!(Err(CipherError::InvalidPadding), cipher.finalize());
+}
+
+#[test]
+fn custom_padding_with_unaligned_output_fails() {
+    let aes = Aes128::new(KEY);
+    let mut cipher = Cipher::init(
+        Operation::Encrypt,
+        &aes,
+        Mode::Ecb,
+        Padding::Custom(Box::new(Truncating)),
+    )
+    .unwrap();
+    cipher.update(&[0x42; 20]).unwrap();
+    assert_eq!(Err(CipherError::InvalidPadding), cipher.finalize());
+}
+
+#[test]
+fn custom_padding_longer_than_block_fails() {
+    let aes = Aes128::new(KEY);
+    let mut cipher = Cipher::init(
+        Operation::Encrypt,
+        &aes,
+        Mode::Ecb,
+        Padding::Custom(Box::new(TwoBlocks)),
+    )
+    .unwrap();
+    assert_eq!(16, cipher.finalize_len());
+    cipher.update(&[0x42; 20]).unwrap();
+    assert_eq!(Err(CipherError::InvalidPadding), cipher.finalize());
+}
+
+#[test]
+fn no_padding_round_trip() {
+    let plaintext: Vec<u8> = (0..48).collect();
+    let ciphertext = encrypt(&plaintext, Padding::NoPadding);
+    assert_eq!(plaintext.len(), ciphertext.len());
+    assert_eq!(&encrypt(&plaintext, Padding::PKCS7)[..48], &ciphertext[..]);
+    assert_eq!(plaintext, decrypt(&ciphertext, Padding::NoPadding));
+
+    assert!(encrypt(&[], Padding::NoPadding).is_empty());
+    assert!(decrypt(&[], Padding::NoPadding).is_empty());
+}
+
+#[test]
+fn no_padding_requires_whole_blocks() {
+    let aes = Aes128::new(KEY);
+    let mut cipher = Cipher::init(Operation::Encrypt, &aes, Mode::Ecb, Padding::NoPadding).unwrap();
+    assert_eq!(16, cipher.update(&[0x42; 20]).unwrap().len());
+    assert_eq!(Err(CipherError::InvalidPadding), cipher.finalize());
+
+    let mut cipher = Cipher::init(Operation::Decrypt, &aes, Mode::Ecb, Padding::NoPadding).unwrap();
+    assert_eq!(16, cipher.update(&[0x42; 20]).unwrap().len());
+    assert_eq!(Err(CipherError::TruncatedInput), cipher.finalize());
+}
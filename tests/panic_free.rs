@@ -0,0 +1,141 @@
+//! Malformed input must be reported as an error, never cause a panic.
+use aes::{Aes128, Aes192, Aes256, Cipher, CipherError, Mode, Operation, Padding};
+
+const KEY: &[u8; 16] = &[0x42; 16];
+
+fn decryptor(aes: &Aes128, mode: Mode) -> Cipher<'_> {
+    Cipher::init(Operation::Decrypt, aes, mode, Padding::PKCS7).unwrap()
+}
+
+fn encrypt(plaintext: &[u8]) -> Vec<u8> {
+    let aes = Aes128::new(KEY);
+    let mut cipher = Cipher::init(Operation::Encrypt, &aes, Mode::Ecb, Padding::PKCS7).unwrap();
+    let mut ciphertext = cipher.update(plaintext).unwrap();
+    ciphertext.extend(cipher.finalize().unwrap());
+    ciphertext
+}
+
+#[test]
+fn invalid_key_length() {
+    assert_eq!(
+        Some(CipherError::InvalidKeyLength { bits: 120 }),
+        Aes128::new_from_slice(&[0; 15]).err()
+    );
+    assert_eq!(
+        Some(CipherError::InvalidKeyLength { bits: 256 }),
+        Aes192::new_from_slice(&[0; 32]).err()
+    );
+    assert_eq!(
+        Some(CipherError::InvalidKeyLength { bits: 0 }),
+        Aes256::new_from_slice(&[]).err()
+    );
+    assert!(Aes256::new_from_slice(&[0; 32]).is_ok());
+}
+
+#[test]
+fn encryption_update_shorter_than_buffered_block() {
+    let aes = Aes128::new(KEY);
+    let mut cipher = Cipher::init(Operation::Encrypt, &aes, Mode::Ecb, Padding::PKCS7).unwrap();
+    let mut ciphertext = Vec::new();
+    for byte in [1u8, 2, 3] {
+        ciphertext.extend(cipher.update(&[byte; 7]).unwrap());
+    }
+    ciphertext.extend(cipher.finalize().unwrap());
+    assert_eq!(encrypt(&[[1u8; 7], [2; 7], [3; 7]].concat()), ciphertext);
+}
+
+#[test]
+fn empty_ciphertext() {
+    let aes = Aes128::new(KEY);
+    let mut cipher = decryptor(&aes, Mode::Ecb);
+    assert!(cipher.update(&[]).unwrap().is_empty());
+    assert_eq!(Err(CipherError::TruncatedInput), cipher.finalize());
+}
+
+#[test]
+fn misaligned_ciphertext() {
+    let ciphertext = encrypt(b"some plaintext longer than a block");
+    for len in [1, 15, 17, ciphertext.len() - 1] {
+        let aes = Aes128::new(KEY);
+        let mut cipher = decryptor(&aes, Mode::Cbc([0; 16]));
+        cipher.update(&ciphertext[..len]).unwrap();
+        assert_eq!(Err(CipherError::TruncatedInput), cipher.finalize());
+    }
+}
+
+#[test]
+fn misaligned_in_place_data() {
+    let aes = Aes128::new(KEY);
+    let mut cipher = decryptor(&aes, Mode::Ecb);
+    assert_eq!(
+        Err(CipherError::UnalignedInput { len: 31 }),
+        cipher.update_in_place(&mut [0; 31])
+    );
+}
+
+#[test]
+fn malformed_padding() {
+    // Last decrypted byte is larger than the block size.
+    let mut block = [0x11; 16];
+    let aes = Aes128::new(KEY);
+    let mut cipher = Cipher::init(Operation::Encrypt, &aes, Mode::Ecb, Padding::PKCS7).unwrap();
+    let ciphertext = cipher.update(&block).unwrap();
+
+    let mut cipher = decryptor(&aes, Mode::Ecb);
+    cipher.update(&ciphertext).unwrap();
+    assert_eq!(Err(CipherError::InvalidPadding), cipher.finalize());
+
+    block[15] = 0xff;
+    let mut cipher = Cipher::init(Operation::Encrypt, &aes, Mode::Ecb, Padding::PKCS7).unwrap();
+    let ciphertext = cipher.update(&block).unwrap();
+    let mut cipher = decryptor(&aes, Mode::Ecb);
+    cipher.update(&ciphertext).unwrap();
+    assert_eq!(Err(CipherError::InvalidPadding), cipher.finalize());
+}
+
+#[test]
+fn too_small_output() {
+    let aes = Aes128::new(KEY);
+    let mut cipher = decryptor(&aes, Mode::Ecb);
+    assert_eq!(
+        Err(CipherError::OutputTooSmall {
+            required: 16,
+            len: 0
+        }),
+        cipher.update_into(&[0; 32], &mut [])
+    );
+}
+
+#[test]
+fn invalid_iv_length() {
+    assert!(matches!(
+        Mode::cbc(&[0; 17]),
+        Err(CipherError::InvalidIvLength { len: 17 })
+    ));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn random_ciphertexts() {
+    let mut seed = 0x9e3779b97f4a7c15u64;
+    let mut next = || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+    let aes = Aes128::new(KEY);
+    for _ in 0..512 {
+        let len = (next() % 80) as usize;
+        let ciphertext: Vec<u8> = (0..len).map(|_| next() as u8).collect();
+        let mut cipher = decryptor(&aes, Mode::Cbc([0; 16]));
+        let mut offset = 0;
+        while offset < ciphertext.len() {
+            let chunk_len = (next() % 20) as usize;
+            let end = (offset + chunk_len).min(ciphertext.len());
+            cipher.update(&ciphertext[offset..end]).unwrap();
+            offset = end;
+        }
+        let _ = cipher.finalize();
+    }
+}
@@ -0,0 +1,41 @@
+use aes::{Aes128, Cipher, Mode, Operation, Padding};
+use std::num::NonZeroUsize;
+
+const KEY: &[u8; 16] = &[
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+];
+
+fn process(operation: Operation, mode: Mode, input: &[u8], threads: usize) -> Vec<u8> {
+    let aes = Aes128::new(KEY);
+    let mut cipher = Cipher::init(operation, &aes, mode, Padding::PKCS7).unwrap();
+    cipher.set_threads(NonZeroUsize::new(threads).unwrap());
+    // Uneven updates, so that chunks handed to the threads start at different offsets.
+    let (first, second) = input.split_at(input.len() / 3 + 5);
+    let mut output = cipher.update(first).unwrap();
+    output.extend(cipher.update(second).unwrap());
+    output.extend(cipher.finalize().unwrap());
+    output
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn parallel_output_is_identical_to_serial() {
+    let plaintext: Vec<u8> = (0..200_000).map(|i: u32| (i * 7 + i / 256) as u8).collect();
+
+    for mode in [|| Mode::Ecb, || Mode::Cbc([0x24; 16])] {
+        let ciphertext = process(Operation::Encrypt, mode(), &plaintext, 1);
+        let recovered = process(Operation::Decrypt, mode(), &ciphertext, 1);
+        assert_eq!(plaintext, recovered);
+
+        for threads in [2, 3, 8] {
+            assert_eq!(
+                ciphertext,
+                process(Operation::Encrypt, mode(), &plaintext, threads)
+            );
+            assert_eq!(
+                plaintext,
+                process(Operation::Decrypt, mode(), &ciphertext, threads)
+            );
+        }
+    }
+}
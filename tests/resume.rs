@@ -0,0 +1,188 @@
+use aes::{Aes128, Aes256, BlockCipher, Checkpoint, Cipher, CipherError, Mode, Operation, Padding};
+
+const IV: [u8; 16] = [0x24; 16];
+
+fn modes() -> [fn() -> Mode; 2] {
+    [|| Mode::Ecb, || Mode::Cbc(IV)]
+}
+
+/// Keys known to the application, found by ID on resume.
+fn keys() -> Vec<(&'static [u8], Box<dyn BlockCipher>)> {
+    vec![
+        (b"key-128", Box::new(Aes128::new(&[0x42; 16]))),
+        (b"key-256", Box::new(Aes256::new(&[0x17; 32]))),
+    ]
+}
+
+fn find_key<'a>(keys: &'a [(&[u8], Box<dyn BlockCipher>)], id: &[u8]) -> &'a dyn BlockCipher {
+    keys.iter()
+        .find(|(key_id, _)| *key_id == id)
+        .unwrap()
+        .1
+        .as_ref()
+}
+
+fn uninterrupted(
+    operation: Operation,
+    key: &dyn BlockCipher,
+    mode: Mode,
+    padding: Padding,
+    input: &[u8],
+) -> Vec<u8> {
+    let mut cipher = Cipher::init(operation, key, mode, padding).unwrap();
+    let mut output = cipher.update(input).unwrap();
+    output.extend(cipher.finalize().unwrap());
+    output
+}
+
+/// Processes `input[..split]`, saves the state as bytes, then resumes and processes the rest.
+fn interrupted(
+    operation: Operation,
+    key_id: &[u8],
+    mode: Mode,
+    padding: Padding,
+    input: &[u8],
+    split: usize,
+) -> Vec<u8> {
+    let before_restart = keys();
+    let key = find_key(&before_restart, key_id);
+    let mut cipher = Cipher::init(operation, key, mode, padding).unwrap();
+    let mut output = cipher.update(&input[..split]).unwrap();
+    let saved = cipher.checkpoint(key_id).unwrap().to_bytes();
+    drop(cipher);
+    drop(before_restart);
+
+    let after_restart = keys();
+    let checkpoint = Checkpoint::from_bytes(&saved).unwrap();
+    let key = find_key(&after_restart, checkpoint.key_id());
+    let mut cipher = Cipher::resume(&checkpoint, key).unwrap();
+    output.extend(cipher.update(&input[split..]).unwrap());
+    output.extend(cipher.finalize().unwrap());
+    output
+}
+
+#[test]
+fn resumed_stream_matches_uninterrupted() {
+    let plaintext: Vec<u8> = (0..70).collect();
+    let keys = keys();
+    for key_id in [&b"key-128"[..], b"key-256"] {
+        let key = find_key(&keys, key_id);
+        for mode in modes() {
+            let ciphertext =
+                uninterrupted(Operation::Encrypt, key, mode(), Padding::PKCS7, &plaintext);
+            for split in 0..=plaintext.len() {
+                let resumed = interrupted(
+                    Operation::Encrypt,
+                    key_id,
+                    mode(),
+                    Padding::PKCS7,
+                    &plaintext,
+                    split,
+                );
+                assert_eq!(ciphertext, resumed, "encryption split at {split}");
+            }
+            for split in 0..=ciphertext.len() {
+                let resumed = interrupted(
+                    Operation::Decrypt,
+                    key_id,
+                    mode(),
+                    Padding::PKCS7,
+                    &ciphertext,
+                    split,
+                );
+                assert_eq!(plaintext, resumed, "decryption split at {split}");
+            }
+        }
+    }
+}
+
+#[test]
+fn unpadded_stream_is_resumed() {
+    let plaintext: Vec<u8> = (0..64).collect();
+    let keys = keys();
+    let key = find_key(&keys, b"key-128");
+    for mode in modes() {
+        let ciphertext = uninterrupted(
+            Operation::Encrypt,
+            key,
+            mode(),
+            Padding::NoPadding,
+            &plaintext,
+        );
+        assert_eq!(plaintext.len(), ciphertext.len());
+        for split in 0..=plaintext.len() {
+            let resumed = interrupted(
+                Operation::Encrypt,
+                b"key-128",
+                mode(),
+                Padding::NoPadding,
+                &plaintext,
+                split,
+            );
+            assert_eq!(ciphertext, resumed, "encryption split at {split}");
+            let resumed = interrupted(
+                Operation::Decrypt,
+                b"key-128",
+                mode(),
+                Padding::NoPadding,
+                &ciphertext,
+                split,
+            );
+            assert_eq!(plaintext, resumed, "decryption split at {split}");
+        }
+    }
+}
+
+#[test]
+fn checkpoint_can_be_resumed_repeatedly() {
+    let key = Aes128::new(&[0x42; 16]);
+    let plaintext: Vec<u8> = (0..200).collect();
+    let expected = uninterrupted(
+        Operation::Encrypt,
+        &key,
+        Mode::Cbc(IV),
+        Padding::PKCS7,
+        &plaintext,
+    );
+
+    let mut cipher = Cipher::init(Operation::Encrypt, &key, Mode::Cbc(IV), Padding::PKCS7).unwrap();
+    let mut output = Vec::new();
+    for chunk in plaintext.chunks(23) {
+        output.extend(cipher.update(chunk).unwrap());
+        let checkpoint = Checkpoint::from_bytes(&cipher.checkpoint(b"id").unwrap().to_bytes());
+        cipher = Cipher::resume(&checkpoint.unwrap(), &key).unwrap();
+    }
+    output.extend(cipher.finalize().unwrap());
+    assert_eq!(expected, output);
+}
+
+#[test]
+fn unsupported_state_is_rejected() {
+    struct Zeroes;
+    impl aes::Padder for Zeroes {
+        fn pad(&self, input: &[u8], output: &mut [u8]) -> Result<usize, CipherError> {
+            output[..input.len()].copy_from_slice(input);
+            output[input.len()..16].fill(0);
+            Ok(16)
+        }
+
+        fn unpad<'a>(&self, input: &'a [u8]) -> Result<&'a [u8], CipherError> {
+            Ok(input)
+        }
+    }
+
+    let key = Aes128::new(&[0x42; 16]);
+    let padding = Padding::Custom(Box::new(Zeroes));
+    let mut cipher = Cipher::init(Operation::Encrypt, &key, Mode::Ecb, padding).unwrap();
+    assert_eq!(
+        Some(CipherError::InvalidState),
+        cipher.checkpoint(b"id").err()
+    );
+
+    let mut cipher = Cipher::init(Operation::Encrypt, &key, Mode::Ecb, Padding::PKCS7).unwrap();
+    assert!(cipher.checkpoint(&[0; 255]).is_ok());
+    assert_eq!(
+        Some(CipherError::InvalidState),
+        cipher.checkpoint(&[0; 256]).err()
+    );
+}
@@ -0,0 +1,108 @@
+use aes::{Aes128, Aes256, BlockCipher, Cipher, CipherError, Key, Mode, Operation, Padding};
+
+fn iv(message: usize) -> [u8; 16] {
+    (message as u128).to_be_bytes()
+}
+
+fn plaintext(message: usize) -> Vec<u8> {
+    (0..message % 40).map(|i| (i * 7 + message) as u8).collect()
+}
+
+fn one_shot(operation: Operation, key: &dyn BlockCipher, mode: Mode, input: &[u8]) -> Vec<u8> {
+    let mut cipher = Cipher::init(operation, key, mode, Padding::PKCS7).unwrap();
+    let mut output = cipher.update(input).unwrap();
+    output.extend(cipher.finalize().unwrap());
+    output
+}
+
+/// Processes a message with a reused cipher, which has to be reset before.
+fn process(cipher: &mut Cipher, input: &[u8]) -> Vec<u8> {
+    let mut output = vec![0u8; cipher.update_len(input.len())];
+    cipher.update_into(input, &mut output).unwrap();
+    let mut last = vec![0u8; cipher.finalize_len()];
+    let len = cipher.finalize_into(&mut last).unwrap();
+    output.extend_from_slice(&last[..len]);
+    output
+}
+
+#[test]
+fn reset_matches_new_cipher_per_message() {
+    let aes = Aes128::new(&[0x42; 16]);
+    let mut encryptor =
+        Cipher::init(Operation::Encrypt, &aes, Mode::Cbc(iv(0)), Padding::PKCS7).unwrap();
+    let mut decryptor =
+        Cipher::init(Operation::Decrypt, &aes, Mode::Cbc(iv(0)), Padding::PKCS7).unwrap();
+    for message in 0..2000 {
+        let plaintext = plaintext(message);
+        encryptor.reset(&iv(message)).unwrap();
+        let ciphertext = process(&mut encryptor, &plaintext);
+        let expected = one_shot(Operation::Encrypt, &aes, Mode::Cbc(iv(message)), &plaintext);
+        assert_eq!(expected, ciphertext, "message {message}");
+
+        decryptor.reset(&iv(message)).unwrap();
+        assert_eq!(plaintext, process(&mut decryptor, &ciphertext));
+    }
+}
+
+#[test]
+fn reset_discards_leftovers() {
+    let aes = Aes128::new(&[0x42; 16]);
+    let mut cipher = Cipher::init(Operation::Encrypt, &aes, Mode::Ecb, Padding::PKCS7).unwrap();
+    cipher.update(&[1; 21]).unwrap();
+    cipher.reset(&[]).unwrap();
+    assert_eq!(
+        one_shot(Operation::Encrypt, &aes, Mode::Ecb, b"next"),
+        process(&mut cipher, b"next")
+    );
+
+    // Decryption fails on the truncated message, next one is not affected.
+    let ciphertext = one_shot(Operation::Encrypt, &aes, Mode::Ecb, b"message");
+    let mut cipher = Cipher::init(Operation::Decrypt, &aes, Mode::Ecb, Padding::PKCS7).unwrap();
+    cipher.update(&ciphertext[..7]).unwrap();
+    assert_eq!(
+        Err(CipherError::TruncatedInput),
+        cipher.finalize_into(&mut [0; 16])
+    );
+    cipher.reset(&[]).unwrap();
+    assert_eq!(b"message", &process(&mut cipher, &ciphertext)[..]);
+}
+
+#[test]
+fn rekey_matches_new_cipher() {
+    let first = Aes128::new(&[0x42; 16]);
+    let second = Aes256::new(&[0x17; 32]);
+    let mut cipher =
+        Cipher::init(Operation::Encrypt, &first, Mode::Cbc(iv(0)), Padding::PKCS7).unwrap();
+    cipher.update(&[1; 5]).unwrap();
+
+    cipher.rekey(&second);
+    cipher.reset(&iv(1)).unwrap();
+    let expected = one_shot(Operation::Encrypt, &second, Mode::Cbc(iv(1)), b"rekeyed");
+    assert_eq!(expected, process(&mut cipher, b"rekeyed"));
+
+    let key = Key::new(&[0x42; 16]).unwrap();
+    let mut owned =
+        Cipher::with_key(Operation::Encrypt, &key, Mode::Cbc(iv(2)), Padding::PKCS7).unwrap();
+    owned.rekey_owned(
+        Key::new(&[0x17; 32])
+            .unwrap()
+            .block_cipher(Default::default()),
+    );
+    owned.reset(&iv(1)).unwrap();
+    assert_eq!(expected, process(&mut owned, b"rekeyed"));
+}
+
+#[test]
+fn reset_validates_iv() {
+    let aes = Aes128::new(&[0x42; 16]);
+    let mut cbc = Cipher::init(Operation::Encrypt, &aes, Mode::Cbc(iv(0)), Padding::PKCS7).unwrap();
+    assert_eq!(
+        Err(CipherError::InvalidIvLength { len: 15 }),
+        cbc.reset(&[0; 15])
+    );
+    let mut ecb = Cipher::init(Operation::Encrypt, &aes, Mode::Ecb, Padding::PKCS7).unwrap();
+    assert_eq!(
+        Err(CipherError::InvalidIvLength { len: 16 }),
+        ecb.reset(&[0; 16])
+    );
+}
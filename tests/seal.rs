@@ -0,0 +1,57 @@
+mod common;
+
+use aes::{open, seal_with, Aes128, Cipher, CipherError, EntropySource, Mode, Operation, Padding};
+use common::Counter;
+
+struct Exhausted;
+
+impl EntropySource for Exhausted {
+    fn fill(&mut self, _: &mut [u8]) -> Result<(), CipherError> {
+        Err(CipherError::EntropyUnavailable)
+    }
+}
+
+#[test]
+fn sealed_message_starts_with_iv() {
+    let aes = Aes128::new(&[0x42; 16]);
+    let plaintext = b"IV is generated and stored in front of the ciphertext.";
+    let mut entropy = Counter(0);
+
+    let sealed = seal_with(&aes, plaintext, &mut entropy).unwrap();
+    assert_eq!([1; 16], sealed[..16]);
+    let mut cipher =
+        Cipher::init(Operation::Encrypt, &aes, Mode::Cbc([1; 16]), Padding::PKCS7).unwrap();
+    let mut expected = cipher.update(plaintext).unwrap();
+    expected.extend(cipher.finalize().unwrap());
+    assert_eq!(expected, sealed[16..]);
+    assert_eq!(plaintext, &open(&aes, &sealed).unwrap()[..]);
+
+    let next = seal_with(&aes, plaintext, &mut entropy).unwrap();
+    assert_eq!([2; 16], next[..16]);
+    assert_eq!(plaintext, &open(&aes, &next).unwrap()[..]);
+}
+
+#[test]
+fn malformed_messages_are_rejected() {
+    let aes = Aes128::new(&[0x42; 16]);
+    let sealed = seal_with(&aes, b"message", &mut Counter(0)).unwrap();
+    assert_eq!(32, sealed.len());
+    assert_eq!(Err(CipherError::TruncatedInput), open(&aes, &sealed[..15]));
+    assert_eq!(Err(CipherError::TruncatedInput), open(&aes, &sealed[..16]));
+    assert_eq!(Err(CipherError::TruncatedInput), open(&aes, &sealed[..31]));
+    assert_eq!(
+        Err(CipherError::EntropyUnavailable),
+        seal_with(&aes, b"message", &mut Exhausted)
+    );
+}
+
+#[cfg(all(feature = "std", unix))]
+#[test]
+fn os_entropy_gives_fresh_ivs() {
+    let aes = Aes128::new(&[0x42; 16]);
+    let first = aes::seal(&aes, b"same message").unwrap();
+    let second = aes::seal(&aes, b"same message").unwrap();
+    assert_ne!(first[..16], second[..16]);
+    assert_ne!(first, second);
+    assert_eq!(open(&aes, &first), open(&aes, &second));
+}
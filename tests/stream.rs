@@ -0,0 +1,154 @@
+mod common;
+
+use aes::{
+    Aes128, Cipher, CipherError, DecryptingReader, EncryptingWriter, Key, Mode, Operation, Padding,
+};
+use common::AnsiX923;
+use std::io::{self, Read, Write};
+
+const KEY: &[u8; 16] = &[
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+];
+const IV: [u8; 16] = [0x24; 16];
+const LENGTHS: &[usize] = &[0, 1, 15, 16, 17, 100, 8191, 8192, 8193, 20000];
+const CHUNKS: &[usize] = &[1, 7, 13, 4097, 10000];
+
+fn configs() -> [fn() -> (Mode, Padding); 4] {
+    [
+        || (Mode::Ecb, Padding::PKCS7),
+        || (Mode::Cbc(IV), Padding::PKCS7),
+        || (Mode::Ecb, Padding::Custom(Box::new(AnsiX923))),
+        || (Mode::Cbc(IV), Padding::Custom(Box::new(AnsiX923))),
+    ]
+}
+
+fn plaintext(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 31 + 7) as u8).collect()
+}
+
+fn one_shot(operation: Operation, config: fn() -> (Mode, Padding), input: &[u8]) -> Vec<u8> {
+    let aes = Aes128::new(KEY);
+    let (mode, padding) = config();
+    let mut cipher = Cipher::init(operation, &aes, mode, padding).unwrap();
+    let mut output = cipher.update(input).unwrap();
+    output.extend(cipher.finalize().unwrap());
+    output
+}
+
+/// Reader returning at most `chunk_len` bytes at once.
+struct Chunked<'a> {
+    data: &'a [u8],
+    chunk_len: usize,
+}
+
+impl Read for Chunked<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.chunk_len).min(self.data.len());
+        buf[..len].copy_from_slice(&self.data[..len]);
+        self.data = &self.data[len..];
+        Ok(len)
+    }
+}
+
+/// Writer accepting at most `chunk_len` bytes at once.
+struct ChunkedWriter {
+    data: Vec<u8>,
+    chunk_len: usize,
+}
+
+impl Write for ChunkedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.chunk_len);
+        self.data.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn writer_matches_one_shot() {
+    let aes = Aes128::new(KEY);
+    for config in configs() {
+        for &len in LENGTHS {
+            let input = plaintext(len);
+            let expected = one_shot(Operation::Encrypt, config, &input);
+            for &chunk_len in CHUNKS {
+                let (mode, padding) = config();
+                let inner = ChunkedWriter {
+                    data: Vec::new(),
+                    chunk_len,
+                };
+                let mut writer = EncryptingWriter::new(inner, &aes, mode, padding).unwrap();
+                for chunk in input.chunks(chunk_len) {
+                    writer.write_all(chunk).unwrap();
+                }
+                let ciphertext = writer.finish().unwrap().data;
+                assert_eq!(expected, ciphertext, "len {len}, chunk {chunk_len}");
+            }
+        }
+    }
+}
+
+#[test]
+fn reader_matches_one_shot() {
+    let aes = Aes128::new(KEY);
+    for config in configs() {
+        for &len in LENGTHS {
+            let expected = plaintext(len);
+            let ciphertext = one_shot(Operation::Encrypt, config, &expected);
+            for &chunk_len in CHUNKS {
+                let (mode, padding) = config();
+                let inner = Chunked {
+                    data: &ciphertext,
+                    chunk_len,
+                };
+                let mut reader = DecryptingReader::new(inner, &aes, mode, padding).unwrap();
+                let mut output = Vec::new();
+                let mut buf = vec![0u8; chunk_len];
+                loop {
+                    let read = reader.read(&mut buf).unwrap();
+                    if read == 0 {
+                        break;
+                    }
+                    output.extend_from_slice(&buf[..read]);
+                }
+                assert_eq!(expected, output, "len {len}, chunk {chunk_len}");
+            }
+        }
+    }
+}
+
+#[test]
+fn large_stream_round_trip() {
+    let key = Key::new(KEY).unwrap();
+    let len = 4 << 20;
+
+    let mut writer =
+        EncryptingWriter::with_key(Vec::new(), &key, Mode::Cbc(IV), Padding::PKCS7).unwrap();
+    io::copy(&mut io::repeat(0x5a).take(len), &mut writer).unwrap();
+    let ciphertext = writer.finish().unwrap();
+    assert_eq!(len as usize + 16, ciphertext.len());
+
+    let mut reader =
+        DecryptingReader::with_key(&ciphertext[..], &key, Mode::Cbc(IV), Padding::PKCS7).unwrap();
+    let mut plaintext = Vec::new();
+    reader.read_to_end(&mut plaintext).unwrap();
+    assert_eq!(len as usize, plaintext.len());
+    assert!(plaintext.iter().all(|&b| b == 0x5a));
+}
+
+#[test]
+fn reader_reports_malformed_ciphertext() {
+    let aes = Aes128::new(KEY);
+    let ciphertext = one_shot(Operation::Encrypt, configs()[1], &plaintext(40));
+
+    let truncated = &ciphertext[..ciphertext.len() - 1];
+    let mut reader = DecryptingReader::new(truncated, &aes, Mode::Cbc(IV), Padding::PKCS7).unwrap();
+    let err = reader.read_to_end(&mut Vec::new()).unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidData, err.kind());
+    let source = err.get_ref().unwrap().downcast_ref::<CipherError>();
+    assert_eq!(Some(&CipherError::TruncatedInput), source);
+}
@@ -0,0 +1,129 @@
+use aes::{
+    Aes128, Block, BlockCipher, BlockMode, Checkpoint, Cipher, CipherError, Key, Mode, Operation,
+    Padding, Transformation,
+};
+
+const IV: [u8; 16] = [0x24; 16];
+
+type Config = fn() -> (Mode, Padding);
+
+/// Mode without a standard name, output feedback with a zero IV.
+struct Ofb;
+
+impl BlockMode for Ofb {
+    fn encrypt(
+        &mut self,
+        blocks: &mut [Block],
+        cipher: &dyn BlockCipher,
+    ) -> Result<(), CipherError> {
+        let mut keystream = [0; 16];
+        for block in blocks {
+            keystream = cipher.encrypt_block(&keystream);
+            for (b, k) in block.iter_mut().zip(keystream) {
+                *b ^= k;
+            }
+        }
+        Ok(())
+    }
+
+    fn decrypt(
+        &mut self,
+        blocks: &mut [Block],
+        cipher: &dyn BlockCipher,
+    ) -> Result<(), CipherError> {
+        self.encrypt(blocks, cipher)
+    }
+}
+
+fn process(mut cipher: Cipher, input: &[u8]) -> Vec<u8> {
+    let mut output = cipher.update(input).unwrap();
+    output.extend(cipher.finalize().unwrap());
+    output
+}
+
+#[test]
+fn transformation_configures_cipher() {
+    let key = Key::new(&[0x42; 32]).unwrap();
+    let plaintext: Vec<u8> = (0..64).collect();
+    let cases: [(&str, &[u8], Config); 3] = [
+        ("AES/ECB/PKCS5Padding", &[], || (Mode::Ecb, Padding::PKCS7)),
+        ("AES_256/CBC/PKCS5Padding", &IV, || {
+            (Mode::Cbc(IV), Padding::PKCS7)
+        }),
+        ("AES/CBC/NoPadding", &IV, || {
+            (Mode::Cbc(IV), Padding::NoPadding)
+        }),
+    ];
+    for (name, iv, config) in cases {
+        let transformation: Transformation = name.parse().unwrap();
+        let (mode, padding) = config();
+        let expected = process(
+            Cipher::with_key(Operation::Encrypt, &key, mode, padding).unwrap(),
+            &plaintext,
+        );
+        let encryptor = transformation.cipher(Operation::Encrypt, &key, iv).unwrap();
+        let ciphertext = process(encryptor, &plaintext);
+        assert_eq!(expected, ciphertext, "{name}");
+
+        let decryptor = transformation.cipher(Operation::Decrypt, &key, iv).unwrap();
+        assert_eq!(plaintext, process(decryptor, &ciphertext), "{name}");
+    }
+}
+
+#[test]
+fn key_length_must_match_algorithm() {
+    let transformation = Transformation::parse("AES_128/CBC/PKCS5Padding").unwrap();
+    let key = Key::new(&[0x42; 24]).unwrap();
+    assert_eq!(
+        Some(CipherError::KeySizeMismatch {
+            expected: 128,
+            actual: 192
+        }),
+        transformation.cipher(Operation::Encrypt, &key, &IV).err()
+    );
+}
+
+#[test]
+fn unpadded_cipher_can_be_checkpointed() {
+    let transformation = Transformation::parse("AES/CBC/NoPadding").unwrap();
+    let key = Key::new(&[0x42; 16]).unwrap();
+    let plaintext = [7u8; 48];
+    let expected = process(
+        transformation
+            .cipher(Operation::Encrypt, &key, &IV)
+            .unwrap(),
+        &plaintext,
+    );
+
+    let mut cipher = transformation
+        .cipher(Operation::Encrypt, &key, &IV)
+        .unwrap();
+    let mut ciphertext = cipher.update(&plaintext[..20]).unwrap();
+    let checkpoint = Checkpoint::from_bytes(&cipher.checkpoint(b"id").unwrap().to_bytes()).unwrap();
+    let aes = Aes128::new(&[0x42; 16]);
+    let mut cipher = Cipher::resume(&checkpoint, &aes).unwrap();
+    assert_eq!(transformation, cipher.transformation().unwrap());
+    ciphertext.extend(cipher.update(&plaintext[20..]).unwrap());
+    ciphertext.extend(cipher.finalize().unwrap());
+    assert_eq!(expected, ciphertext);
+}
+
+#[test]
+fn cipher_formats_transformation() {
+    let aes = Aes128::new(&[0x42; 16]);
+    let mut cipher =
+        Cipher::init(Operation::Decrypt, &aes, Mode::Cbc(IV), Padding::NoPadding).unwrap();
+    let transformation = cipher.transformation().unwrap();
+    assert_eq!("AES/CBC/NoPadding", transformation.to_string());
+    assert_eq!(
+        "AES_128/CBC/NoPadding",
+        transformation.with_key_bits(128).unwrap().to_string()
+    );
+
+    let mode = Mode::Custom(Box::new(Ofb));
+    let mut cipher = Cipher::init(Operation::Encrypt, &aes, mode, Padding::PKCS7).unwrap();
+    assert!(matches!(
+        cipher.transformation(),
+        Err(CipherError::InvalidTransformation { .. })
+    ));
+}
@@ -0,0 +1,134 @@
+use aes::{
+    Aes128, Cipher, CipherError, KeyUsage, MeteredCipher, Mode, Operation, Padding, UsageLimits,
+};
+use std::sync::{Arc, Mutex};
+
+const IV: [u8; 16] = [0x24; 16];
+
+fn encrypt(cipher: &mut Cipher, plaintext: &[u8]) -> Result<Vec<u8>, CipherError> {
+    let mut output = vec![0u8; cipher.update_len(plaintext.len())];
+    cipher.update_into(plaintext, &mut output)?;
+    let mut last = vec![0u8; cipher.finalize_len()];
+    let len = cipher.finalize_into(&mut last)?;
+    output.extend_from_slice(&last[..len]);
+    Ok(output)
+}
+
+#[test]
+fn encryption_is_counted() {
+    let key = MeteredCipher::new(Aes128::new(&[0x42; 16]), UsageLimits::default());
+    let mut cipher = Cipher::init(Operation::Encrypt, &key, Mode::Cbc(IV), Padding::PKCS7).unwrap();
+    let ciphertext = encrypt(&mut cipher, &[1; 40]).unwrap();
+    cipher.reset(&IV).unwrap();
+    encrypt(&mut cipher, &[1; 16]).unwrap();
+
+    let mut ecb = Cipher::init(Operation::Encrypt, &key, Mode::Ecb, Padding::NoPadding).unwrap();
+    ecb.update_in_place(&mut [0; 64]).unwrap();
+
+    let expected = KeyUsage {
+        messages: 3,
+        ecb_blocks: 4,
+        cbc_blocks: 5,
+        custom_blocks: 0,
+    };
+    assert_eq!(expected, key.usage());
+
+    // Decryption doesn't use up the key.
+    let mut decryptor =
+        Cipher::init(Operation::Decrypt, &key, Mode::Cbc(IV), Padding::PKCS7).unwrap();
+    decryptor.update(&ciphertext).unwrap();
+    decryptor.finalize().unwrap();
+    assert_eq!(expected, key.usage());
+}
+
+#[test]
+fn block_limit_is_enforced_per_mode() {
+    let limits = UsageLimits {
+        cbc_blocks: 4,
+        ecb_blocks: 0,
+        ..UsageLimits::UNLIMITED
+    };
+    let key = MeteredCipher::new(Aes128::new(&[0x42; 16]), limits);
+    let mut cipher = Cipher::init(Operation::Encrypt, &key, Mode::Cbc(IV), Padding::PKCS7).unwrap();
+    assert_eq!(48, encrypt(&mut cipher, &[1; 40]).unwrap().len());
+
+    // Update exceeding the limit has no effect, input isn't taken and output isn't written.
+    cipher.reset(&IV).unwrap();
+    let mut output = [0xaa; 48];
+    assert_eq!(
+        Err(CipherError::KeyExhausted),
+        cipher.update_into(&[1; 40], &mut output)
+    );
+    assert_eq!([0xaa; 48], output);
+    let mut data = [1; 32];
+    assert_eq!(
+        Err(CipherError::KeyExhausted),
+        cipher.update_in_place(&mut data)
+    );
+    assert_eq!([1; 32], data);
+    assert_eq!(0, cipher.update_len(0));
+
+    let expected = {
+        let aes = Aes128::new(&[0x42; 16]);
+        let mut cipher =
+            Cipher::init(Operation::Encrypt, &aes, Mode::Cbc(IV), Padding::PKCS7).unwrap();
+        cipher.update(&[1; 16]).unwrap()
+    };
+    assert_eq!(expected, cipher.update(&[1; 16]).unwrap());
+    assert_eq!(Err(CipherError::KeyExhausted), cipher.finalize());
+    assert_eq!(4, key.usage().cbc_blocks);
+
+    let ecb = Cipher::init(Operation::Encrypt, &key, Mode::Ecb, Padding::PKCS7).unwrap();
+    assert_eq!(Err(CipherError::KeyExhausted), ecb.finalize());
+}
+
+#[test]
+fn message_limit_is_enforced() {
+    let limits = UsageLimits {
+        messages: 2,
+        ..UsageLimits::default()
+    };
+    let key = MeteredCipher::new(Aes128::new(&[0x42; 16]), limits);
+    let mut cipher = Cipher::init(Operation::Encrypt, &key, Mode::Cbc(IV), Padding::PKCS7).unwrap();
+    encrypt(&mut cipher, b"first").unwrap();
+    assert_eq!(
+        Err(CipherError::InvalidIvLength { len: 8 }),
+        cipher.reset(&[0; 8])
+    );
+    // Rejected IV doesn't use up the last message.
+    cipher.reset(&IV).unwrap();
+    encrypt(&mut cipher, b"second").unwrap();
+    assert_eq!(Err(CipherError::KeyExhausted), cipher.reset(&IV));
+    assert_eq!(
+        Some(CipherError::KeyExhausted),
+        Cipher::init(Operation::Encrypt, &key, Mode::Ecb, Padding::PKCS7).err()
+    );
+    assert!(Cipher::init(Operation::Decrypt, &key, Mode::Ecb, Padding::PKCS7).is_ok());
+
+    // Usage restored after a restart counts towards the same limits.
+    let restored = MeteredCipher::new(Aes128::new(&[0x42; 16]), limits).with_usage(key.usage());
+    assert_eq!(
+        Some(CipherError::KeyExhausted),
+        Cipher::init(Operation::Encrypt, &restored, Mode::Ecb, Padding::PKCS7).err()
+    );
+}
+
+#[test]
+fn usage_is_reported() {
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let sink = reports.clone();
+    let key = MeteredCipher::new(Aes128::new(&[0x42; 16]), UsageLimits::default())
+        .on_usage(move |usage| sink.lock().unwrap().push(*usage));
+
+    let mut cipher = Cipher::init(Operation::Encrypt, &key, Mode::Ecb, Padding::PKCS7).unwrap();
+    cipher.update(&[1; 20]).unwrap();
+    cipher.update(&[1; 4]).unwrap();
+    cipher.finalize().unwrap();
+
+    let reports = reports.lock().unwrap();
+    let ecb_blocks: Vec<(u64, u64)> = reports
+        .iter()
+        .map(|usage| (usage.messages, usage.ecb_blocks))
+        .collect();
+    assert_eq!(vec![(1, 0), (1, 1), (1, 2)], ecb_blocks);
+}